@@ -0,0 +1,118 @@
+//! Brute-force search benchmarks. Run with `cargo bench --bench search`.
+//!
+//! Each case is warmed up, then timed over a number of samples; the report
+//! shows the fastest, median and slowest time per iteration, criterion-style.
+
+use assistant_agent::knowledge::documents::Document;
+use assistant_agent::knowledge::embeddings::{cosine_similarity, dot, normalize};
+use assistant_agent::knowledge::filter::SearchFilter;
+use assistant_agent::knowledge::index::VectorIndex;
+use assistant_agent::knowledge::quantization::{Quantization, QuantizedVector};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const DIM: usize = 768;
+const K: usize = 10;
+const MODEL: &str = "bench";
+const SAMPLES: usize = 20;
+const WARM_UP: Duration = Duration::from_millis(300);
+
+fn main() {
+    let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+    let a = rng.vector(DIM);
+    let b = rng.vector(DIM);
+    bench("similarity/cosine", || cosine_similarity(black_box(&a), black_box(&b)));
+    bench("similarity/dot", || dot(black_box(&a), black_box(&b)));
+
+    for size in [1_000, 10_000, 100_000] {
+        let documents = corpus(&mut rng, size, Quantization::None);
+        let mut indexed = documents.clone();
+        let index = VectorIndex::build(&mut indexed);
+        let query = rng.vector(DIM);
+        let filter = SearchFilter::default();
+
+        bench(&format!("search/naive/{}", size), || naive_top_k(&documents, &query, K));
+        bench(&format!("search/index/{}", size), || {
+            index.top_k(&indexed, MODEL, black_box(&query), &filter, K)
+        });
+
+        let mut documents = corpus(&mut rng, size, Quantization::Int8);
+        let index = VectorIndex::build(&mut documents);
+        bench(&format!("search/index-int8/{}", size), || {
+            index.top_k(&documents, MODEL, black_box(&query), &filter, K)
+        });
+    }
+}
+
+/// The search this index replaced: cosine similarity against every document
+/// followed by a full sort.
+fn naive_top_k(documents: &[Document], query: &[f32], k: usize) -> Vec<(f32, usize)> {
+    let mut scored: Vec<(f32, usize)> = documents
+        .iter()
+        .enumerate()
+        .filter_map(|(i, doc)| Some((cosine_similarity(query, doc.embedding.as_ref()?), i)))
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.truncate(k);
+    scored
+}
+
+fn corpus(rng: &mut XorShift, size: usize, quantization: Quantization) -> Vec<Document> {
+    (0..size)
+        .map(|i| {
+            let mut doc = Document::new(format!("chunk {}", i), "bench".to_string());
+            let vector = rng.vector(DIM);
+            doc.quantized = QuantizedVector::encode(&vector, quantization);
+            doc.embedding = if doc.quantized.is_some() { None } else { Some(vector) };
+            doc.embedding_model = Some(MODEL.to_string());
+            doc
+        })
+        .collect()
+}
+
+fn bench<T>(name: &str, mut f: impl FnMut() -> T) {
+    let started = Instant::now();
+    let mut iterations = 0u64;
+    while started.elapsed() < WARM_UP {
+        black_box(f());
+        iterations += 1;
+    }
+    // Size samples to roughly 50ms each
+    let per_sample = (iterations * 50 / WARM_UP.as_millis() as u64).max(1);
+
+    let mut samples: Vec<Duration> = (0..SAMPLES)
+        .map(|_| {
+            let started = Instant::now();
+            for _ in 0..per_sample {
+                black_box(f());
+            }
+            started.elapsed() / per_sample as u32
+        })
+        .collect();
+    samples.sort();
+    println!(
+        "{:<28} time: [{:>10.3?} {:>10.3?} {:>10.3?}]",
+        name,
+        samples[0],
+        samples[SAMPLES / 2],
+        samples[SAMPLES - 1]
+    );
+}
+
+/// Small deterministic generator so runs are comparable without a `rand` dependency.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0
+    }
+
+    fn vector(&mut self, dim: usize) -> Vec<f32> {
+        let mut vector: Vec<f32> = (0..dim).map(|_| self.next()).collect();
+        normalize(&mut vector);
+        vector
+    }
+}
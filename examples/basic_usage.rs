@@ -0,0 +1,19 @@
+use assistant_agent::{agent::assistant::Assistant, config::settings::Settings};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let settings = Settings::new()?;
+    let mut assistant = Assistant::new(settings).await?;
+
+    assistant
+        .learn_text("Rust is a systems programming language focused on safety and performance.", "example")
+        .await?;
+
+    let reply = assistant.chat("What do you know about Rust?").await?;
+    println!("{}", reply.content);
+    for citation in &reply.citations {
+        println!("[{}] {}", citation.number, citation.source);
+    }
+
+    Ok(())
+}
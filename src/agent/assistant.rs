@@ -1,21 +1,71 @@
 use crate::config::settings::Settings;
-use crate::knowledge::documents::{Document, chunk_text};
-use crate::knowledge::embeddings::EmbeddingService;
-use crate::knowledge::vectorstore::VectorStore;
+use crate::knowledge::cache::{CacheStats, EmbeddingCache};
+use crate::knowledge::collections::{CollectionInfo, Collections, DEFAULT_COLLECTION};
+use crate::knowledge::decay::TimeDecay;
+use crate::knowledge::documents::{Document, chunk_text, chunk_text_with_offsets, content_hash};
+use crate::knowledge::extractors::ExtractedSection;
+use crate::knowledge::embeddings::{Embedding, EmbeddingService};
+use crate::knowledge::hashing::HashingEmbedder;
+use crate::knowledge::graph::{Entity, GraphExtractor, GraphStats, KnowledgeGraph, Neighbourhood, format_facts};
+use crate::knowledge::ingest::{embed_concurrently, map_concurrently};
+use crate::knowledge::extractors::ExtractorRegistry;
+use crate::knowledge::expansion::expand_hits;
+use crate::knowledge::filter::SearchFilter;
+use crate::knowledge::fusion::reciprocal_rank_fusion;
+use crate::knowledge::interchange::{ConflictPolicy, ImportReport, read_documents, write_documents};
+use crate::knowledge::manifest::EmbeddingStatus;
+use crate::knowledge::mmr::mmr_select;
+use crate::knowledge::quantization::VectorMemory;
+use crate::knowledge::rerank::{RerankReport, Reranker};
+use crate::knowledge::vectorstore::{ScoredDocument, SearchOptions, SourceSummary, StoreStats, SyncReport};
+use crate::knowledge::watcher::{FolderWatcher, WatchRegistry, WatchStatus};
 use crate::llm::ollama::OllamaClient;
+use crate::memory::episodic::{EpisodicMemory, format_episodes};
 use crate::memory::storage::{ConversationManager, load_persistent_conversation, save_persistent_conversation};
-use crate::agent::personality::PersonalityProfile;
+use crate::agent::personality::{PersonalityProfile, PromptContext};
+use crate::agent::rewrite::{QueryRewriter, RewrittenQuery};
+use crate::utils::file::{WalkEntry, WalkOptions, walk_files};
 use crate::{AssistantError, Result};
-use super::chain::{format_context, build_messages};
+use super::evaluation::{EvalCase, EvalConfig, EvalReport, QueryResult, distinct_sources};
+use super::chain::{format_context, build_messages, parse_citations};
 
 use chrono::Utc;
+use indicatif::ProgressBar;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Chunks re-embedded per [`Assistant::reembed_step`] call.
+const REEMBED_BATCH: usize = 16;
+
+/// Entities mentioned in a message whose relations are added to the prompt.
+const GRAPH_ENTITIES: usize = 5;
+/// Most knowledge graph facts added to the prompt.
+const GRAPH_FACTS: usize = 20;
 
 pub struct Assistant {
     settings: Settings,
     ollama: OllamaClient,
-    vectorstore: VectorStore,
+    collections: Collections,
+    /// Collection that learning commands write to unless told otherwise.
+    learn_target: String,
+    /// Collections chat retrieves from; empty means all of them.
+    search_collections: Vec<String>,
     conversation: ConversationManager,
+    /// Past exchanges recalled by similarity; `None` when disabled.
+    episodes: Option<EpisodicMemory>,
     personality: PersonalityProfile,
+    watch_registry: WatchRegistry,
+    watcher: Option<FolderWatcher>,
+    extractors: ExtractorRegistry,
+    search_filter: SearchFilter,
+    reranker: Reranker,
+    last_rerank: Option<RerankReport>,
+    rewriter: QueryRewriter,
+    last_queries: Option<RewrittenQuery>,
+    /// Entities and relations extracted while learning; `None` when disabled.
+    graph: Option<KnowledgeGraph>,
+    extractor: GraphExtractor,
 }
 
 impl Assistant {
@@ -29,13 +79,56 @@ impl Assistant {
             return Err(AssistantError::ModelNotFound(settings.ollama_model.clone()));
         }
         
-        let embedding_service = EmbeddingService::new(
+        let reranker = Reranker::new(
+            ollama.clone(),
+            settings.rerank_mode,
+            settings.rerank_model.clone(),
+            settings.rerank_concurrency,
+        );
+        
+        let rewriter = QueryRewriter::new(
+            ollama.clone(),
+            settings.ollama_model.clone(),
+            settings.query_rewrite,
+            settings.query_expansion,
+            settings.query_variants,
+        );
+        
+        let mut embedding_service = EmbeddingService::new(
             OllamaClient::new(settings.ollama_host.clone()),
             settings.embedding_model.clone(),
-        );
+        )
+        .with_backend(settings.embedding_backend, HashingEmbedder::new(settings.local_embedding_dim));
+        if settings.embedding_cache {
+            let cache = EmbeddingCache::open(&settings.knowledge_dir.join("embedding_cache"))?;
+            embedding_service = embedding_service.with_cache(cache);
+        }
         
-        let storage_path = settings.knowledge_dir.join("documents.json");
-        let vectorstore = VectorStore::new(storage_path, embedding_service);
+        let episodes = if settings.episodic_memory {
+            Some(EpisodicMemory::open(
+                settings.conversations_dir.join("episodes.json"),
+                embedding_service.clone(),
+                settings.quantization,
+                settings.keep_full_vectors,
+            )?)
+        } else {
+            None
+        };
+        
+        let collections = Collections::open(
+            settings.knowledge_dir.clone(),
+            embedding_service,
+            settings.quantization,
+            settings.keep_full_vectors,
+        )?
+        .with_time_decay(settings.time_decay_half_life_days, settings.time_decay_weight);
+        
+        let graph = if settings.graph_extraction {
+            Some(KnowledgeGraph::open(&settings.knowledge_dir.join("graph"))?)
+        } else {
+            None
+        };
+        let extractor = GraphExtractor::new(ollama.clone(), settings.graph_model.clone());
         
         // Load personality profile
         let personality = PersonalityProfile::load_or_create(&settings.data_dir)?;
@@ -44,26 +137,80 @@ impl Assistant {
         let history = load_persistent_conversation(&settings.data_dir)?;
         let conversation = ConversationManager::new_with_history(settings.max_history, history);
         
+        // Resume watching registered folders; a watcher failure only disables live sync
+        let mut watch_registry = WatchRegistry::load_or_default(&settings.data_dir)?;
+        let mut watcher = FolderWatcher::new(Duration::from_millis(settings.watch_debounce_ms)).ok();
+        if let Some(watcher) = watcher.as_mut() {
+            for folder in watch_registry.folders.iter_mut() {
+                folder.last_error = watcher.watch(&folder.path).err().map(|e| e.to_string());
+            }
+        }
+        
         Ok(Self {
             settings,
             ollama,
-            vectorstore,
+            collections,
+            learn_target: DEFAULT_COLLECTION.to_string(),
+            search_collections: Vec::new(),
             conversation,
+            episodes,
             personality,
+            watch_registry,
+            watcher,
+            extractors: ExtractorRegistry::default(),
+            search_filter: SearchFilter::default(),
+            reranker,
+            last_rerank: None,
+            rewriter,
+            last_queries: None,
+            graph,
+            extractor,
         })
     }
     
-    pub async fn chat(&mut self, user_message: &str) -> Result<String> {
-        // Search knowledge base
-        let relevant_docs = self.vectorstore.search(user_message, self.settings.retrieval_k).await?;
-        let context_strings: Vec<String> = relevant_docs.iter()
-            .map(|doc| doc.content.clone())
+    pub async fn chat(&mut self, user_message: &str) -> Result<ChatReply> {
+        // Search with a standalone version of the message so follow-ups find something
+        let history = self.conversation.get_recent_llm_messages();
+        let rewritten = self.rewriter.rewrite(&history, user_message).await;
+        let relevant_docs = self.retrieve_all(&rewritten.queries).await?;
+        let episodes = match &self.episodes {
+            // Past exchanges only add context; answer without them if recall fails
+            Some(memory) => memory
+                .recall(
+                    &rewritten.queries[0],
+                    self.settings.episodic_k,
+                    self.settings.episodic_min_similarity,
+                    self.conversation.oldest_timestamp(),
+                )
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!("could not recall past conversations: {}", e);
+                    Vec::new()
+                }),
+            None => Vec::new(),
+        };
+        let graph_facts = self.graph_facts(&rewritten.queries[0])?;
+        self.last_queries = Some(rewritten);
+        let relevant_docs = expand_hits(
+            relevant_docs,
+            self.settings.context_expansion,
+            self.settings.context_window,
+            |parent_id| self.collections.family(parent_id),
+        );
+        let context_docs: Vec<&Document> = relevant_docs.iter()
+            .map(|hit| &hit.document)
             .collect();
-        let context = format_context(&context_strings);
+        
+        // Leave the knowledge section out entirely when nothing was relevant
+        let context = (!context_docs.is_empty()).then(|| format_context(&context_docs, Utc::now()));
+        let past_conversations = (!episodes.is_empty()).then(|| format_episodes(&episodes, Utc::now()));
         
         // Build personalized system prompt
-        let system_prompt = self.personality.build_system_prompt(&context);
-        let history = self.conversation.get_recent_llm_messages();
+        let system_prompt = self.personality.build_system_prompt(PromptContext {
+            knowledge: context.as_deref(),
+            episodes: past_conversations.as_deref(),
+            graph: graph_facts.as_deref(),
+        });
         let messages = build_messages(system_prompt, history, user_message.to_string());
         
         // Get response
@@ -80,6 +227,14 @@ impl Assistant {
         // Save conversation to disk
         let all_messages = self.conversation.export();
         save_persistent_conversation(&all_messages, &self.settings.data_dir)?;
+        // The reply already exists; failing to remember it must not lose it
+        let mut warning = None;
+        if let Some(memory) = self.episodes.as_mut() {
+            if let Err(e) = memory.remember(user_message, &response).await {
+                tracing::warn!("could not add the exchange to episodic memory: {}", e);
+                warning = Some(format!("I couldn't file this exchange in my long-term memory: {}", e));
+            }
+        }
         
         // Learn from the conversation (add to memory context)
         if user_message.len() > 10 {
@@ -91,36 +246,916 @@ impl Assistant {
             self.personality.save(&self.settings.data_dir)?;
         }
         
-        Ok(response)
+        let citations = parse_citations(&response, context_docs.len())
+            .into_iter()
+            .map(|number| Citation::new(number, context_docs[number - 1]))
+            .collect();
+        
+        Ok(ChatReply {
+            content: response,
+            citations,
+            warning,
+        })
+    }
+    
+    /// Runs the retrieval pipeline for `query`: vector search, then optional
+    /// reranking of a wider candidate pool, then MMR diversification.
+    pub async fn retrieve(&mut self, query: &str) -> Result<Vec<ScoredDocument>> {
+        self.retrieve_all(&[query.to_string()]).await
     }
     
-    pub async fn learn_text(&mut self, text: &str, source: &str) -> Result<()> {
-        let chunks = chunk_text(text, self.settings.chunk_size, self.settings.chunk_overlap);
+    /// [`Assistant::retrieve`] over several phrasings of one request. Each
+    /// query's candidate pool is searched and the pools are fused with
+    /// reciprocal rank fusion before reranking, which uses the first query.
+    pub async fn retrieve_all(&mut self, queries: &[String]) -> Result<Vec<ScoredDocument>> {
+        let options = self.search_options();
+        self.retrieve_with(queries, options).await
+    }
+    
+    /// Runs each labelled query through the same rewriting and retrieval
+    /// stages as chat, without conversation history, and scores the top `k`
+    /// sources against the expected ones.
+    pub async fn evaluate(&mut self, cases: &[EvalCase], k: usize) -> Result<EvalReport> {
+        let mut options = self.search_options();
+        let mut results = Vec::with_capacity(cases.len());
+        for case in cases {
+            let rewritten = self.rewriter.rewrite(&[], &case.query).await;
+            // Several chunks may share a source, so fetch until k distinct
+            // sources turn up or the knowledge base has no more to give
+            let mut fetch = k.max(1);
+            let retrieved = loop {
+                options.k = fetch;
+                let hits = self.retrieve_with(&rewritten.queries, options.clone()).await?;
+                let exhausted = hits.len() < fetch;
+                let sources = distinct_sources(hits.into_iter().map(|hit| hit.document.metadata.source));
+                if sources.len() >= k || exhausted {
+                    break sources;
+                }
+                fetch *= 2;
+            };
+            results.push(QueryResult::score(case, retrieved, k));
+        }
         
-        let documents: Vec<Document> = chunks
-            .into_iter()
-            .map(|chunk| Document::new(chunk, source.to_string()))
+        let collections = self.searched_collections();
+        let time_decay = collections
+            .iter()
+            .filter_map(|name| Some((name.clone(), self.collections.decay_for(name)?)))
             .collect();
         
-        self.vectorstore.add_documents(documents).await?;
+        let config = EvalConfig {
+            embedding_model: self.collections.embedding_service().model().to_string(),
+            chunk_size: self.settings.chunk_size,
+            chunk_overlap: self.settings.chunk_overlap,
+            min_similarity: self.settings.min_similarity,
+            mmr_lambda: self.settings.mmr_lambda,
+            fetch_k: self.settings.retrieval_fetch_k,
+            rerank_mode: self.settings.rerank_mode,
+            query_expansion: self.settings.query_expansion,
+            collections,
+            filter: (!self.search_filter.is_empty()).then(|| self.search_filter.to_string()),
+            time_decay,
+            context_expansion: self.settings.context_expansion,
+            context_window: self.settings.context_window,
+            retrieval_k: self.settings.retrieval_k,
+        };
+        Ok(EvalReport::new(k, config, results))
+    }
+    
+    async fn retrieve_with(&mut self, queries: &[String], mut options: SearchOptions) -> Result<Vec<ScoredDocument>> {
+        let names = self.searched_collections();
+        let Some(primary) = queries.first() else {
+            return Ok(Vec::new());
+        };
+        if queries.len() == 1 && !self.reranker.is_enabled() {
+            return self.collections.search(&names, primary, &options).await;
+        }
         
+        // Rerank the whole pool first so MMR diversifies what the reranker preferred
+        let k = options.k;
+        options.k = options.fetch_k.max(k);
+        options.mmr_lambda = 1.0;
+        let mut pools = Vec::with_capacity(queries.len());
+        for query in queries {
+            pools.push(self.collections.search(&names, query, &options).await?);
+        }
+        let mut candidates = reciprocal_rank_fusion(pools);
+        candidates.truncate(options.k);
+        
+        let mut ranked = if self.reranker.is_enabled() {
+            let (reranked, report) = self.reranker.rerank(primary, candidates).await;
+            self.last_rerank = Some(report);
+            reranked
+        } else {
+            candidates
+        };
+        
+        if self.settings.mmr_lambda < 1.0 {
+            Ok(mmr_select(ranked, k, self.settings.mmr_lambda))
+        } else {
+            ranked.truncate(k);
+            Ok(ranked)
+        }
+    }
+    
+    pub fn collections(&self) -> Vec<CollectionInfo> {
+        self.collections.info()
+    }
+    
+    /// Every learned source with the collection holding it, by collection.
+    pub fn list_sources(&self) -> Vec<(String, SourceSummary)> {
+        self.collections
+            .stores()
+            .flat_map(|(name, store)| store.source_summaries().into_iter().map(move |s| (name.clone(), s)))
+            .collect()
+    }
+    
+    /// The chunks learned from `source` in any collection, in chunk order,
+    /// with the collection each is in.
+    pub fn source_chunks(&self, source: &str) -> Vec<(String, &Document)> {
+        let mut chunks: Vec<(String, &Document)> = self
+            .collections
+            .stores()
+            .flat_map(|(name, store)| store.documents_for_source(source).into_iter().map(move |d| (name.clone(), d)))
+            .collect();
+        chunks.sort_by_key(|(name, doc)| (name.clone(), doc.metadata.parent_id.clone(), doc.metadata.chunk_index));
+        chunks
+    }
+    
+    /// The top `k` chunks for `query` straight from vector search over the
+    /// searched collections: no rewriting, filter, reranking, MMR or
+    /// similarity cutoff.
+    pub async fn raw_search(&self, query: &str, k: usize) -> Result<Vec<ScoredDocument>> {
+        self.collections.search(&self.searched_collections(), query, &SearchOptions::new(k)).await
+    }
+    
+    pub fn store_stats(&self) -> Vec<(String, StoreStats)> {
+        self.collections.stores().map(|(name, store)| (name.clone(), store.stats())).collect()
+    }
+    
+    /// Sets how quickly chunks in `collection` lose rank as they age:
+    /// `Some(days)` overrides the configured half-life (0 turns decay off for
+    /// the collection) and `None` returns to it.
+    pub fn set_half_life(&mut self, collection: &str, half_life_days: Option<f32>) -> Result<Option<TimeDecay>> {
+        self.collections.set_half_life(collection, half_life_days)
+    }
+    
+    /// Creates an empty collection, returning false if it already exists.
+    pub fn create_collection(&mut self, name: &str) -> Result<bool> {
+        self.collections.create(name)
+    }
+    
+    /// Deletes a collection and everything learned into it, returning the
+    /// number of chunks dropped. Folders watched into it stop being watched.
+    pub fn drop_collection(&mut self, name: &str) -> Result<usize> {
+        let sources = self.collections.get(name)?.sources();
+        let removed = self.collections.drop_collection(name)?;
+        self.forget_in_graph(&sources)?;
+        
+        if self.learn_target == name {
+            self.learn_target = DEFAULT_COLLECTION.to_string();
+        }
+        self.search_collections.retain(|c| c != name);
+        
+        let orphaned: Vec<PathBuf> = self
+            .watch_registry
+            .folders
+            .iter()
+            .filter(|f| f.collection == name)
+            .map(|f| f.path.clone())
+            .collect();
+        for root in &orphaned {
+            self.watch_registry.remove(root);
+            if let Some(watcher) = self.watcher.as_mut() {
+                let _ = watcher.unwatch(root);
+            }
+        }
+        if !orphaned.is_empty() {
+            self.watch_registry.save(&self.settings.data_dir)?;
+        }
+        Ok(removed)
+    }
+    
+    pub fn learn_target(&self) -> &str {
+        &self.learn_target
+    }
+    
+    /// Makes `name` the collection learning commands write to by default.
+    pub fn set_learn_target(&mut self, name: &str) -> Result<()> {
+        self.collections.get(name)?;
+        self.learn_target = name.to_string();
         Ok(())
     }
     
-    pub async fn learn_file(&mut self, filepath: &str) -> Result<()> {
-        let content = std::fs::read_to_string(filepath)
-            .map_err(|e| AssistantError::IoError(e))?;
+    /// Restricts chat retrieval to `names`; an empty list searches every collection.
+    pub fn set_search_collections(&mut self, names: Vec<String>) -> Result<()> {
+        for name in &names {
+            self.collections.get(name)?;
+        }
+        self.search_collections = names;
+        Ok(())
+    }
+    
+    /// Chunks retrieved per chat message.
+    pub fn retrieval_k(&self) -> usize {
+        self.settings.retrieval_k
+    }
+    
+    /// Collections chat currently retrieves from.
+    pub fn searched_collections(&self) -> Vec<String> {
+        if self.search_collections.is_empty() {
+            self.collections.names()
+        } else {
+            self.search_collections.clone()
+        }
+    }
+    
+    pub fn embedding_status(&self) -> EmbeddingStatus {
+        let mut statuses = vec![self.collections.embedding_status()];
+        statuses.extend(self.episodes.as_ref().map(|memory| memory.embedding_status()));
+        EmbeddingStatus::combine(self.collections.embedding_service().model(), &statuses)
+    }
+    
+    /// Starts migrating every chunk to the configured embedding model. The
+    /// work itself happens in [`Assistant::reembed_step`].
+    pub fn start_reembed(&mut self) -> Result<EmbeddingStatus> {
+        for (_, store) in self.collections.stores_mut() {
+            store.begin_reembed()?;
+        }
+        if let Some(memory) = self.episodes.as_mut() {
+            memory.store_mut().begin_reembed()?;
+        }
+        Ok(self.embedding_status())
+    }
+    
+    /// Re-embeds the next batch of chunks if a migration is under way,
+    /// returning the updated status, or `None` when there is nothing to do.
+    /// Progress is saved after every batch, so a migration interrupted by
+    /// quitting resumes on the next run.
+    pub async fn reembed_step(&mut self) -> Result<Option<EmbeddingStatus>> {
+        let migrating = self
+            .collections
+            .stores_mut()
+            .map(|(_, store)| store)
+            .chain(self.episodes.as_mut().map(|memory| memory.store_mut()))
+            .find(|store| store.embedding_status().is_migrating());
+        let Some(store) = migrating else {
+            return Ok(None);
+        };
+        store.reembed_batch(REEMBED_BATCH).await?;
+        Ok(Some(self.embedding_status()))
+    }
+    
+    /// `name` and its relations in the knowledge graph, or `None` if no
+    /// such entity was extracted.
+    pub fn entity_neighbourhood(&self, name: &str) -> Result<Option<Neighbourhood>> {
+        self.knowledge_graph()?.neighbourhood(name)
+    }
+    
+    /// Entities whose name contains `text` with their number of relations,
+    /// most connected first. An empty `text` lists them all.
+    pub fn search_entities(&self, text: &str, limit: usize) -> Result<Vec<(Entity, usize)>> {
+        self.knowledge_graph()?.search(text, limit)
+    }
+    
+    fn knowledge_graph(&self) -> Result<&KnowledgeGraph> {
+        self.graph.as_ref().ok_or_else(|| {
+            AssistantError::KnowledgeError("the knowledge graph is off; set GRAPH_EXTRACTION=true".to_string())
+        })
+    }
+    
+    /// Restricts knowledge retrieval for subsequent chats, e.g. to one folder
+    /// or tag. An empty filter searches everything again.
+    pub fn set_search_filter(&mut self, filter: SearchFilter) {
+        self.search_filter = filter;
+    }
+    
+    pub fn search_filter(&self) -> &SearchFilter {
+        &self.search_filter
+    }
+    
+    /// Tags and annotates everything learned from sources starting with
+    /// `source_prefix`, returning the number of chunks updated.
+    pub fn annotate_source(
+        &mut self,
+        source_prefix: &str,
+        tags: &[String],
+        fields: &[(String, String)],
+    ) -> Result<usize> {
+        let mut updated = 0;
+        for (_, store) in self.collections.stores_mut() {
+            updated += store.annotate(source_prefix, tags, fields)?;
+        }
+        Ok(updated)
+    }
+    
+    /// Number of chunks learned from `source`, across collections.
+    pub fn source_chunk_count(&self, source: &str) -> usize {
+        self.collections
+            .stores()
+            .map(|(_, store)| store.documents_for_source(source).len())
+            .sum()
+    }
+    
+    pub fn get_knowledge(&self, id: &str) -> Option<&Document> {
+        self.collections.stores().find_map(|(_, store)| store.get(id))
+    }
+    
+    /// Forgets everything learned from `source` in any collection, returning
+    /// how many chunks were removed.
+    pub fn forget_source(&mut self, source: &str) -> Result<usize> {
+        let mut removed = 0;
+        for (_, store) in self.collections.stores_mut() {
+            removed += store.remove_source(source)?;
+        }
+        self.forget_in_graph(&[source.to_string()])?;
+        Ok(removed)
+    }
+    
+    /// Forgets a single chunk by id.
+    pub fn forget_knowledge(&mut self, id: &str) -> Result<bool> {
+        for (_, store) in self.collections.stores_mut() {
+            if store.delete(id)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+    
+    /// Rewrites a single chunk by id and re-embeds it.
+    pub async fn update_knowledge(&mut self, id: &str, content: &str) -> Result<bool> {
+        let owner = self.collections.stores_mut().find(|(_, store)| store.get(id).is_some());
+        match owner {
+            Some((_, store)) => store.update(id, content.to_string()).await,
+            None => Ok(false),
+        }
+    }
+    
+    /// Learns `text` under `source` into the current learn target, returning
+    /// how many new chunks were stored. Chunks already known for the same
+    /// source are skipped.
+    pub async fn learn_text(&mut self, text: &str, source: &str) -> Result<usize> {
+        let collection = self.learn_target.clone();
+        self.learn_text_in(&collection, text, source, &ProgressBar::hidden()).await
+    }
+    
+    /// [`Assistant::learn_text`] into a specific collection, advancing
+    /// `progress` as chunks are embedded.
+    pub async fn learn_text_in(
+        &mut self,
+        collection: &str,
+        text: &str,
+        source: &str,
+        progress: &ProgressBar,
+    ) -> Result<usize> {
+        let documents = self.chunk_documents(text, source);
+        let pending = self.collections.get(collection)?.pending_chunks(&documents);
+        progress.set_length(pending.len() as u64);
+        progress.set_prefix("Embedding");
+        progress.set_message("chunks");
         
-        self.learn_text(&content, filepath).await
+        let embeddings = self.embed_chunks(&documents, &pending, progress).await?;
+        let chunks: Vec<GraphChunk> = pending
+            .iter()
+            .map(|&i| GraphChunk::new(source, &documents[i]))
+            .collect();
+        let store = self.collections.get_mut(collection)?;
+        let added = store.add_embedded(documents, embeddings)?;
+        if added > 0 {
+            store.flush()?;
+        }
+        self.update_graph(chunks, progress).await?;
+        Ok(added)
     }
     
-    pub async fn export_knowledge(&self, output_path: &str) -> Result<()> {
-        let docs = self.vectorstore.export()?;
-        let json = serde_json::to_string_pretty(&docs)
-            .map_err(|e| AssistantError::SerializationError(e.to_string()))?;
+    /// Learns a file into the current learn target, replacing whatever was
+    /// previously learned from the same path. Re-learning an unchanged file
+    /// does nothing. PDF, HTML and DOCX files are converted to text first,
+    /// keeping page and section numbers.
+    pub async fn learn_file(&mut self, filepath: &str) -> Result<SyncReport> {
+        let collection = self.learn_target.clone();
+        self.learn_file_in(&collection, filepath, &ProgressBar::hidden()).await
+    }
+    
+    /// [`Assistant::learn_file`] into a specific collection, advancing
+    /// `progress` as chunks are embedded.
+    pub async fn learn_file_in(&mut self, collection: &str, filepath: &str, progress: &ProgressBar) -> Result<SyncReport> {
+        let file = self.prepare_file(collection, Path::new(filepath))?;
+        progress.set_length(file.pending.len() as u64);
+        progress.set_prefix("Embedding");
+        progress.set_message("chunks");
         
-        std::fs::write(output_path, json)?;
-        Ok(())
+        let mut results = Vec::new();
+        self.ingest_prepared(collection, vec![(0, file)], &mut results, progress).await;
+        match results.pop().map(|(_, outcome)| outcome) {
+            Some(FileOutcome::Learned(report)) => Ok(report),
+            Some(FileOutcome::Failed(e)) => Err(AssistantError::KnowledgeError(e)),
+            _ => Ok(SyncReport::default()),
+        }
+    }
+    
+    /// Learns every text file under `dir` into the current learn target,
+    /// honouring ignore files and the configured include/exclude globs and
+    /// size limit. Failures on individual files are reported rather than
+    /// aborting the walk.
+    pub async fn learn_dir(&mut self, dir: &str) -> Result<Vec<FileIngestResult>> {
+        let collection = self.learn_target.clone();
+        self.learn_dir_in(&collection, dir, &ProgressBar::hidden()).await
+    }
+    
+    /// [`Assistant::learn_dir`] into a specific collection, advancing
+    /// `progress` first over the files read and then over the chunks embedded.
+    pub async fn learn_dir_in(&mut self, collection: &str, dir: &str, progress: &ProgressBar) -> Result<Vec<FileIngestResult>> {
+        self.collections.get(collection)?;
+        let entries = walk_files(Path::new(dir), &self.walk_options())
+            .map_err(|e| AssistantError::KnowledgeError(e.to_string()))?;
+        self.ingest_files(collection, entries, progress).await
+    }
+    
+    /// Starts keeping `dir` in sync with the current learn target and
+    /// performs an initial sync of its contents.
+    pub async fn watch_folder(&mut self, dir: &str) -> Result<Vec<FileIngestResult>> {
+        let collection = self.learn_target.clone();
+        self.watch_folder_in(&collection, dir, &ProgressBar::hidden()).await
+    }
+    
+    /// [`Assistant::watch_folder`] into a specific collection, advancing
+    /// `progress` during the initial sync.
+    pub async fn watch_folder_in(&mut self, collection: &str, dir: &str, progress: &ProgressBar) -> Result<Vec<FileIngestResult>> {
+        self.collections.get(collection)?;
+        let root = std::fs::canonicalize(dir)?;
+        if !root.is_dir() {
+            return Err(AssistantError::KnowledgeError(format!("{} is not a directory", dir)));
+        }
+        
+        if self.watch_registry.add(root.clone(), collection) {
+            if let Some(watcher) = self.watcher.as_mut() {
+                watcher.watch(&root)?;
+            }
+            self.watch_registry.save(&self.settings.data_dir)?;
+        }
+        
+        self.resync_folder(&root, progress).await
+    }
+    
+    /// Stops watching `dir`. Knowledge already learned from it is kept.
+    pub fn unwatch_folder(&mut self, dir: &str) -> Result<bool> {
+        let root = std::fs::canonicalize(dir).unwrap_or_else(|_| PathBuf::from(dir));
+        if !self.watch_registry.remove(&root) {
+            return Ok(false);
+        }
+        
+        if let Some(watcher) = self.watcher.as_mut() {
+            let _ = watcher.unwatch(&root);
+        }
+        self.watch_registry.save(&self.settings.data_dir)?;
+        Ok(true)
+    }
+    
+    /// Brings every watched folder up to date, picking up changes made while
+    /// the assistant was not running.
+    pub async fn resync_watched(&mut self, progress: &ProgressBar) -> Result<Vec<FileIngestResult>> {
+        let roots: Vec<PathBuf> = self.watch_registry.folders.iter().map(|f| f.path.clone()).collect();
+        let mut results = Vec::new();
+        for root in roots {
+            results.extend(self.resync_folder(&root, progress).await?);
+        }
+        Ok(results)
+    }
+    
+    /// Applies filesystem changes that have settled since the last call.
+    /// Cheap to call often; returns an empty list when nothing is ready.
+    pub async fn sync_watched_changes(&mut self) -> Result<Vec<FileIngestResult>> {
+        let ready = match self.watcher.as_mut() {
+            Some(watcher) => watcher.take_ready(),
+            None => return Ok(Vec::new()),
+        };
+        
+        let mut by_root: Vec<(PathBuf, Vec<PathBuf>)> = Vec::new();
+        for path in ready {
+            let Some(root) = self.watch_registry.root_of(&path) else { continue };
+            match by_root.iter_mut().find(|(r, _)| *r == root) {
+                Some((_, paths)) => paths.push(path),
+                None => by_root.push((root, vec![path])),
+            }
+        }
+        
+        let mut results = Vec::new();
+        for (root, changed) in by_root {
+            let Some(collection) = self.watch_registry.get(&root).map(|f| f.collection.clone()) else {
+                continue;
+            };
+            let candidates: Vec<PathBuf> = walk_files(&root, &self.walk_options())
+                .map_err(|e| AssistantError::KnowledgeError(e.to_string()))?
+                .into_iter()
+                .filter_map(|entry| match entry {
+                    WalkEntry::File(path) => Some(path),
+                    WalkEntry::Skipped(..) => None,
+                })
+                .collect();
+            
+            let mut to_learn = Vec::new();
+            for path in changed {
+                let files: Vec<&PathBuf> = candidates.iter().filter(|c| c.starts_with(&path)).collect();
+                if files.is_empty() {
+                    // Deleted, renamed away or no longer eligible
+                    let removed = self.forget_under(&collection, &path)?;
+                    if removed > 0 {
+                        results.push(FileIngestResult {
+                            path: path.display().to_string(),
+                            outcome: FileOutcome::Removed(removed),
+                        });
+                    }
+                    continue;
+                }
+                to_learn.extend(files.into_iter().map(|file| WalkEntry::File(file.clone())));
+            }
+            let learned = self.ingest_files(&collection, to_learn, &ProgressBar::hidden()).await?;
+            let synced = learned.iter().filter(|r| matches!(r.outcome, FileOutcome::Learned(_))).count();
+            results.extend(learned);
+            
+            if let Some(folder) = self.watch_registry.get_mut(&root) {
+                folder.last_sync = Some(Utc::now());
+                folder.files_synced += synced;
+            }
+        }
+        
+        if !results.is_empty() {
+            self.watch_registry.save(&self.settings.data_dir)?;
+        }
+        Ok(results)
+    }
+    
+    pub fn watch_status(&mut self) -> WatchStatus {
+        let (pending_changes, active) = match self.watcher.as_mut() {
+            Some(watcher) => (watcher.pending_count(), true),
+            None => (0, false),
+        };
+        WatchStatus {
+            folders: self.watch_registry.folders.clone(),
+            pending_changes,
+            active,
+        }
+    }
+    
+    /// Learns everything under `root` and drops knowledge from files that
+    /// are no longer there.
+    async fn resync_folder(&mut self, root: &Path, progress: &ProgressBar) -> Result<Vec<FileIngestResult>> {
+        let collection = self
+            .watch_registry
+            .get(root)
+            .map(|f| f.collection.clone())
+            .unwrap_or_else(|| DEFAULT_COLLECTION.to_string());
+        let mut results = match self.learn_dir_in(&collection, &root.display().to_string(), progress).await {
+            Ok(results) => results,
+            Err(e) => {
+                if let Some(folder) = self.watch_registry.get_mut(root) {
+                    folder.last_error = Some(e.to_string());
+                }
+                self.watch_registry.save(&self.settings.data_dir)?;
+                return Err(e);
+            }
+        };
+        
+        // A file that failed to read this time keeps what was learned before
+        let present: HashSet<&str> = results
+            .iter()
+            .filter(|r| matches!(r.outcome, FileOutcome::Learned(_) | FileOutcome::Failed(_)))
+            .map(|r| r.path.as_str())
+            .collect();
+        let store = self.collections.get_mut(&collection)?;
+        let stale: Vec<String> = store
+            .sources()
+            .into_iter()
+            .filter(|s| Path::new(s).starts_with(root) && !present.contains(s.as_str()))
+            .collect();
+        let removed = store.remove_sources(&stale)?;
+        self.forget_in_graph(&stale)?;
+        let synced = results.iter().filter(|r| matches!(r.outcome, FileOutcome::Learned(_))).count();
+        if removed > 0 {
+            results.push(FileIngestResult {
+                path: root.display().to_string(),
+                outcome: FileOutcome::Removed(removed),
+            });
+        }
+        
+        if let Some(folder) = self.watch_registry.get_mut(root) {
+            folder.last_sync = Some(Utc::now());
+            folder.files_synced = synced;
+            folder.last_error = None;
+        }
+        self.watch_registry.save(&self.settings.data_dir)?;
+        Ok(results)
+    }
+    
+    /// Forgets every source in `collection` at or below `path`.
+    fn forget_under(&mut self, collection: &str, path: &Path) -> Result<usize> {
+        let store = self.collections.get_mut(collection)?;
+        let sources: Vec<String> = store
+            .sources()
+            .into_iter()
+            .filter(|s| Path::new(s).starts_with(path))
+            .collect();
+        let removed = store.remove_sources(&sources)?;
+        self.forget_in_graph(&sources)?;
+        Ok(removed)
+    }
+    
+    /// Learns the files among `entries`, reading and chunking them as they
+    /// are reached: new chunks are embedded [`Settings::ingest_concurrency`]
+    /// at a time and written every [`Settings::ingest_batch_size`] chunks, so
+    /// only one batch is held in memory and an interrupted run keeps what it
+    /// finished. Results follow `entries`; a file that can't be learned is
+    /// reported as failed and the rest carry on.
+    async fn ingest_files(
+        &mut self,
+        collection: &str,
+        entries: Vec<WalkEntry>,
+        progress: &ProgressBar,
+    ) -> Result<Vec<FileIngestResult>> {
+        progress.set_length(0);
+        progress.set_prefix("Embedding");
+        progress.set_message("chunks");
+        
+        let mut results = Vec::with_capacity(entries.len());
+        let mut outcomes = Vec::new();
+        let mut batch = Vec::new();
+        let mut batch_chunks = 0;
+        for entry in entries {
+            let (path, outcome) = match entry {
+                WalkEntry::Skipped(path, reason) => (path, Some(FileOutcome::Skipped(reason))),
+                WalkEntry::File(path) if !self.extractors.supports(&path) => {
+                    (path, Some(FileOutcome::Skipped("unsupported file type".to_string())))
+                }
+                WalkEntry::File(path) => match self.prepare_file(collection, &path) {
+                    Ok(file) => {
+                        progress.inc_length(file.pending.len() as u64);
+                        batch_chunks += file.pending.len();
+                        batch.push((results.len(), file));
+                        (path, None)
+                    }
+                    Err(e) => (path, Some(FileOutcome::Failed(e.to_string()))),
+                },
+            };
+            results.push(FileIngestResult {
+                path: path.display().to_string(),
+                outcome: outcome.unwrap_or(FileOutcome::Learned(SyncReport::default())),
+            });
+            if batch_chunks >= self.settings.ingest_batch_size {
+                self.ingest_prepared(collection, std::mem::take(&mut batch), &mut outcomes, progress).await;
+                batch_chunks = 0;
+            }
+        }
+        if !batch.is_empty() {
+            self.ingest_prepared(collection, batch, &mut outcomes, progress).await;
+        }
+        
+        for (index, outcome) in outcomes {
+            results[index].outcome = outcome;
+        }
+        Ok(results)
+    }
+    
+    /// Embeds the new chunks of a batch of prepared files together, then
+    /// stores each file and writes the collection once. With the knowledge
+    /// graph on, changed files then have their entities re-extracted. Any
+    /// error is reported against the files it affects.
+    async fn ingest_prepared(
+        &mut self,
+        collection: &str,
+        batch: Vec<(usize, PreparedFile)>,
+        outcomes: &mut Vec<(usize, FileOutcome)>,
+        progress: &ProgressBar,
+    ) {
+        let texts: Vec<String> = batch
+            .iter()
+            .flat_map(|(_, file)| file.pending.iter().map(|&i| file.documents[i].content.clone()))
+            .collect();
+        let embedded = embed_concurrently(
+            self.collections.embedding_service(),
+            texts,
+            self.settings.ingest_concurrency,
+            progress,
+        )
+        .await;
+        let (mut embedded, store) = match (embedded, self.collections.get_mut(collection)) {
+            (Ok(embedded), Ok(store)) => (embedded.into_iter(), store),
+            (Err(e), _) | (_, Err(e)) => {
+                outcomes.extend(batch.into_iter().map(|(index, _)| (index, FileOutcome::Failed(e.to_string()))));
+                return;
+            }
+        };
+        
+        let mut learned = Vec::new();
+        let mut graph_chunks = Vec::new();
+        for (index, file) in batch {
+            let embeddings: Result<Vec<Embedding>> = embedded.by_ref().take(file.pending.len()).collect();
+            // A changed file is re-extracted whole; unchanged chunks come from the cache
+            let chunks: Vec<GraphChunk> = match &self.graph {
+                Some(_) => file.documents.iter().map(|doc| GraphChunk::new(&file.source, doc)).collect(),
+                None => Vec::new(),
+            };
+            let applied = embeddings.and_then(|embeddings| {
+                let embeddings = file.pending.iter().copied().zip(embeddings).collect();
+                store.apply_source(&file.source, &file.file_hash, file.documents, embeddings)
+            });
+            let forgotten = match (&applied, &self.graph) {
+                (Ok(_), Some(graph)) if !chunks.is_empty() => graph.remove_source(&file.source).map(|_| ()),
+                _ => Ok(()),
+            };
+            match (applied, forgotten) {
+                (Ok(report), Ok(())) => {
+                    graph_chunks.extend(chunks);
+                    learned.push((index, report));
+                }
+                (Err(e), _) | (_, Err(e)) => outcomes.push((index, FileOutcome::Failed(e.to_string()))),
+            }
+        }
+        
+        if let Err(e) = store.flush() {
+            outcomes.extend(learned.into_iter().map(|(index, _)| (index, FileOutcome::Failed(e.to_string()))));
+            return;
+        }
+        let graphed = self.update_graph(graph_chunks, progress).await;
+        outcomes.extend(learned.into_iter().map(|(index, report)| {
+            let outcome = match &graphed {
+                Ok(()) => FileOutcome::Learned(report),
+                Err(e) => FileOutcome::Failed(format!("learned, but updating the knowledge graph failed: {}", e)),
+            };
+            (index, outcome)
+        }));
+    }
+    
+    /// Reads, extracts and chunks a file for [`Assistant::ingest_files`]. An
+    /// unchanged file yields no chunks.
+    fn prepare_file(&self, collection: &str, path: &Path) -> Result<PreparedFile> {
+        let source = path.display().to_string();
+        let bytes = std::fs::read(path)?;
+        let file_hash = content_hash(&bytes);
+        let store = self.collections.get(collection)?;
+        if store.source_hash(&source) == Some(file_hash.as_str()) {
+            return Ok(PreparedFile { source, file_hash, documents: Vec::new(), pending: Vec::new() });
+        }
+        
+        let mut documents = Vec::new();
+        for section in self.extractors.extract(path, &bytes)? {
+            documents.extend(chunk_section(&section, &source, self.settings.chunk_size, self.settings.chunk_overlap));
+        }
+        let parent_id = uuid::Uuid::new_v4().to_string();
+        let documents: Vec<Document> = documents
+            .into_iter()
+            .enumerate()
+            .map(|(i, doc)| doc.with_position(parent_id.clone(), i))
+            .collect();
+        let pending = store.pending_chunks(&documents);
+        Ok(PreparedFile { source, file_hash, documents, pending })
+    }
+    
+    /// Embeds the chunks of `documents` at the `pending` indices.
+    async fn embed_chunks(
+        &self,
+        documents: &[Document],
+        pending: &[usize],
+        progress: &ProgressBar,
+    ) -> Result<HashMap<usize, Embedding>> {
+        let texts = pending.iter().map(|&i| documents[i].content.clone()).collect();
+        let embedded = embed_concurrently(
+            self.collections.embedding_service(),
+            texts,
+            self.settings.ingest_concurrency,
+            progress,
+        )
+        .await?;
+        pending.iter().copied().zip(embedded).map(|(i, e)| e.map(|e| (i, e))).collect()
+    }
+    
+    /// Adds what `chunks` say about entities and their relations to the
+    /// knowledge graph, asking the model only about chunks it hasn't seen.
+    /// A chunk the model fails on is left out rather than failing the
+    /// ingest.
+    async fn update_graph(&mut self, chunks: Vec<GraphChunk>, progress: &ProgressBar) -> Result<()> {
+        let Some(graph) = &self.graph else {
+            return Ok(());
+        };
+        if chunks.is_empty() {
+            return Ok(());
+        }
+        
+        let model = self.extractor.model().to_string();
+        let mut uncached = Vec::new();
+        for chunk in chunks {
+            match graph.cached_extraction(&model, &chunk.content_hash) {
+                Some(extraction) => graph.add(&chunk.source, &extraction)?,
+                None => uncached.push(chunk),
+            }
+        }
+        
+        if !uncached.is_empty() {
+            progress.inc_length(uncached.len() as u64);
+            progress.set_prefix("Graphing");
+            let extractor = &self.extractor;
+            let extracted = map_concurrently(uncached, self.settings.ingest_concurrency, progress, |chunk| {
+                let extractor = extractor.clone();
+                async move {
+                    let extraction = extractor.extract(&chunk.content).await;
+                    (chunk, extraction)
+                }
+            })
+            .await?;
+            progress.set_prefix("Embedding");
+            for (chunk, extraction) in extracted {
+                if let Ok(extraction) = extraction {
+                    graph.cache_extraction(&model, &chunk.content_hash, &extraction)?;
+                    graph.add(&chunk.source, &extraction)?;
+                }
+            }
+        }
+        graph.flush()
+    }
+    
+    /// Drops `sources` no collection still holds from the knowledge graph.
+    fn forget_in_graph(&self, sources: &[String]) -> Result<()> {
+        let Some(graph) = &self.graph else {
+            return Ok(());
+        };
+        for source in sources {
+            let held = self.collections.stores().any(|(_, store)| !store.documents_for_source(source).is_empty());
+            if !held {
+                graph.remove_source(source)?;
+            }
+        }
+        graph.flush()
+    }
+    
+    /// Facts about the entities `text` mentions, for the system prompt. Only
+    /// facts stated by sources chat can currently retrieve, given the
+    /// searched collections and the search filter, are included.
+    fn graph_facts(&self, text: &str) -> Result<Option<String>> {
+        let Some(graph) = &self.graph else {
+            return Ok(None);
+        };
+        let mut in_scope = HashSet::new();
+        for name in self.searched_collections() {
+            let store = self.collections.get(&name)?;
+            in_scope.extend(
+                store
+                    .documents()
+                    .filter(|doc| self.search_filter.matches(&doc.metadata))
+                    .map(|doc| doc.metadata.source.as_str()),
+            );
+        }
+        
+        let mut neighbourhoods = Vec::new();
+        for name in graph.mentioned_in(text)? {
+            if neighbourhoods.len() == GRAPH_ENTITIES {
+                break;
+            }
+            let scoped = graph.neighbourhood(&name)?.and_then(|hood| hood.within(|source| in_scope.contains(source)));
+            neighbourhoods.extend(scoped);
+        }
+        let facts = format_facts(&neighbourhoods, GRAPH_FACTS);
+        Ok((!facts.is_empty()).then_some(facts))
+    }
+    
+    fn search_options(&self) -> SearchOptions {
+        SearchOptions {
+            k: self.settings.retrieval_k,
+            min_score: self.settings.min_similarity,
+            filter: self.search_filter.clone(),
+            mmr_lambda: self.settings.mmr_lambda,
+            fetch_k: self.settings.retrieval_fetch_k,
+        }
+    }
+    
+    fn walk_options(&self) -> WalkOptions {
+        WalkOptions {
+            include: self.settings.include_globs.clone(),
+            exclude: self.settings.exclude_globs.clone(),
+            max_file_size: self.settings.max_file_size,
+        }
+    }
+    
+    fn chunk_documents(&self, text: &str, source: &str) -> Vec<Document> {
+        let parent_id = uuid::Uuid::new_v4().to_string();
+        chunk_text(text, self.settings.chunk_size, self.settings.chunk_overlap)
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunk)| Document::new(chunk, source.to_string()).with_position(parent_id.clone(), i))
+            .collect()
+    }
+    
+    /// Exports the learn target collection as a JSON array, or as JSON Lines
+    /// when the path ends in `.jsonl`. Returns the number of chunks written.
+    pub async fn export_knowledge(&self, output_path: &str, include_embeddings: bool) -> Result<usize> {
+        let store = self.collections.get(&self.learn_target)?;
+        write_documents(Path::new(output_path), store.documents_with_vectors(), include_embeddings)
+    }
+    
+    /// Imports a file written by [`Assistant::export_knowledge`], here or on
+    /// another machine, into the learn target collection.
+    pub async fn import_knowledge(&mut self, input_path: &str, policy: ConflictPolicy) -> Result<ImportReport> {
+        let docs = read_documents(Path::new(input_path))?;
+        let store = self.collections.get_mut(&self.learn_target)?;
+        store.import_documents(docs, policy).await
     }
     
     pub async fn clear_history(&mut self) -> Result<()> {
@@ -165,18 +1200,98 @@ impl Assistant {
         
         Ok(AssistantInfo {
             model: self.settings.ollama_model.clone(),
-            embedding_model: self.settings.embedding_model.clone(),
-            knowledge_count: self.vectorstore.count(),
+            embedding_model: self.collections.embedding_service().model().to_string(),
+            knowledge_count: self.collections.stores().map(|(_, store)| store.count()).sum(),
             conversation_count: self.conversation.count(),
             ollama_available,
             data_dir: self.settings.data_dir.display().to_string(),
             personality_name: self.personality.name.clone(),
             user_name: self.personality.user_preferences.name.clone(),
             memories_count: self.personality.memory_context.len(),
+            episodes_count: self.episodes.as_ref().map(|memory| memory.count()),
+            graph: self.graph.as_ref().map(|graph| graph.stats()),
+            last_rerank: self.last_rerank.clone(),
+            embedding_status: self.embedding_status(),
+            vector_memory: self.collections.vector_memory(),
+            embedding_cache: self.collections.embedding_service().cache_stats(),
+            last_queries: self.last_queries.clone(),
+            learn_target: self.learn_target.clone(),
+            searched_collections: self.searched_collections(),
         })
     }
 }
 
+/// A reply from [`Assistant::chat`] and the knowledge it cited.
+#[derive(Debug, Clone)]
+pub struct ChatReply {
+    pub content: String,
+    pub citations: Vec<Citation>,
+    /// Set when something after the reply was generated failed.
+    pub warning: Option<String>,
+}
+
+/// A numbered knowledge entry referenced in a reply.
+#[derive(Debug, Clone)]
+pub struct Citation {
+    pub number: usize,
+    pub document_id: String,
+    pub source: String,
+    pub location: Option<String>,
+}
+
+impl Citation {
+    fn new(number: usize, doc: &Document) -> Self {
+        Self {
+            number,
+            document_id: doc.id.clone(),
+            source: doc.metadata.source.clone(),
+            location: doc.metadata.location(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum FileOutcome {
+    Learned(SyncReport),
+    Skipped(String),
+    Failed(String),
+    /// Chunks dropped because their file was deleted or is no longer eligible.
+    Removed(usize),
+}
+
+/// A learned chunk waiting for entity and relation extraction.
+struct GraphChunk {
+    source: String,
+    content_hash: String,
+    content: String,
+}
+
+impl GraphChunk {
+    fn new(source: &str, doc: &Document) -> Self {
+        Self {
+            source: source.to_string(),
+            content_hash: doc.metadata.content_hash.clone(),
+            content: doc.content.clone(),
+        }
+    }
+}
+
+/// A file read and chunked by [`Assistant::prepare_file`], waiting for its
+/// new chunks to be embedded.
+struct PreparedFile {
+    source: String,
+    file_hash: String,
+    documents: Vec<Document>,
+    /// Indices of the chunks that need embedding.
+    pending: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileIngestResult {
+    pub path: String,
+    pub outcome: FileOutcome,
+}
+
 pub struct AssistantInfo {
     pub model: String,
     pub embedding_model: String,
@@ -187,5 +1302,87 @@ pub struct AssistantInfo {
     pub personality_name: String,
     pub user_name: Option<String>,
     pub memories_count: usize,
+    /// Exchanges in episodic memory, or `None` when it is disabled.
+    pub episodes_count: Option<usize>,
+    /// Size of the knowledge graph, or `None` when extraction is off.
+    pub graph: Option<GraphStats>,
+    pub last_rerank: Option<RerankReport>,
+    pub embedding_status: EmbeddingStatus,
+    pub vector_memory: VectorMemory,
+    pub embedding_cache: Option<CacheStats>,
+    pub last_queries: Option<RewrittenQuery>,
+    pub learn_target: String,
+    pub searched_collections: Vec<String>,
 }
 
+/// Chunks one extracted section, recording its page, heading and, when the
+/// extractor kept the file's lines, the line range of each chunk.
+fn chunk_section(section: &ExtractedSection, source: &str, chunk_size: usize, overlap: usize) -> Vec<Document> {
+    let chunks = chunk_text_with_offsets(&section.text, chunk_size, overlap);
+    
+    // Line number at each char offset, from the newlines before it
+    let mut line_at = Vec::with_capacity(section.text.len() + 1);
+    let mut line = section.first_line.unwrap_or(1);
+    for c in section.text.chars() {
+        line_at.push(line);
+        if c == '\n' {
+            line += 1;
+        }
+    }
+    line_at.push(line);
+    
+    chunks
+        .into_iter()
+        .map(|(start, chunk)| {
+            let doc = Document::new(chunk.clone(), source.to_string())
+                .with_location(section.page, section.section.clone());
+            if section.first_line.is_none() {
+                return doc;
+            }
+            let leading = chunk.chars().take_while(|c| c.is_whitespace()).count();
+            let trailing = chunk.chars().rev().take_while(|c| c.is_whitespace()).count();
+            let end = (start + chunk.chars().count()).saturating_sub(trailing + 1).max(start + leading);
+            doc.with_lines(line_at[start + leading], line_at[end])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(text: &str, first_line: Option<usize>) -> ExtractedSection {
+        ExtractedSection {
+            text: text.to_string(),
+            page: Some(2),
+            section: Some("Notes".to_string()),
+            first_line,
+        }
+    }
+
+    fn lines(docs: &[Document]) -> Vec<(Option<usize>, Option<usize>)> {
+        docs.iter().map(|doc| (doc.metadata.line_start, doc.metadata.line_end)).collect()
+    }
+
+    #[test]
+    fn chunk_section_maps_chunks_to_file_lines() {
+        let docs = chunk_section(&section("alpha\nbeta\ngamma\ndelta\n", Some(10)), "notes.md", 12, 0);
+        assert_eq!(docs.len(), 2);
+        assert_eq!(lines(&docs), vec![(Some(10), Some(12)), (Some(12), Some(13))]);
+        assert!(docs.iter().all(|doc| doc.metadata.page == Some(2) && doc.metadata.source == "notes.md"));
+    }
+
+    #[test]
+    fn chunk_section_skips_surrounding_blank_lines() {
+        let docs = chunk_section(&section("one\n\n\ntwo", Some(1)), "notes.md", 5, 0);
+        assert_eq!(docs.len(), 2);
+        assert_eq!(lines(&docs), vec![(Some(1), Some(1)), (Some(4), Some(4))]);
+    }
+
+    #[test]
+    fn chunk_section_leaves_lines_unset_without_a_first_line() {
+        let docs = chunk_section(&section("alpha\nbeta", None), "report.pdf", 100, 0);
+        assert_eq!(lines(&docs), vec![(None, None)]);
+        assert_eq!(docs[0].metadata.section.as_deref(), Some("Notes"));
+    }
+}
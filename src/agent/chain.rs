@@ -1,4 +1,8 @@
+use crate::knowledge::documents::Document;
+use chrono::{DateTime, Utc};
 use crate::llm::types::Message;
+use regex::Regex;
+use std::sync::OnceLock;
 
 pub fn build_system_prompt(knowledge_context: &str) -> String {
     format!(
@@ -10,12 +14,68 @@ pub fn build_system_prompt(knowledge_context: &str) -> String {
     )
 }
 
-pub fn format_context(documents: &[String]) -> String {
+/// Numbers each document as `[n] source (location), learned <date> (<age>)`
+/// followed by its content, so the model can cite it and weigh how current
+/// it is.
+pub fn format_context(documents: &[&Document], now: DateTime<Utc>) -> String {
     if documents.is_empty() {
-        "No relevant information in knowledge base.".to_string()
-    } else {
-        documents.join("\n\n")
+        return "No relevant information in knowledge base.".to_string();
     }
+    
+    documents
+        .iter()
+        .enumerate()
+        .map(|(i, doc)| {
+            format!(
+                "[{}] {}, learned {} ({})\n{}",
+                i + 1,
+                source_label(doc),
+                doc.metadata.timestamp.format("%Y-%m-%d"),
+                format_age(doc.metadata.timestamp, now),
+                doc.content.trim()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// `source (location)`, or just the source when the location is unknown.
+pub fn source_label(doc: &Document) -> String {
+    match doc.metadata.location() {
+        Some(location) => format!("{} ({})", doc.metadata.source, location),
+        None => doc.metadata.source.clone(),
+    }
+}
+
+/// How long before `now` `timestamp` was, e.g. "3 days ago".
+pub fn format_age(timestamp: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let age = now - timestamp;
+    let plural = |n: i64, unit: &str| format!("{} {}{} ago", n, unit, if n == 1 { "" } else { "s" });
+    match age.num_days() {
+        _ if age.num_hours() < 1 => "just now".to_string(),
+        0 => plural(age.num_hours(), "hour"),
+        1 => "yesterday".to_string(),
+        days @ 2..=59 => plural(days, "day"),
+        days @ 60..=729 => plural(days / 30, "month"),
+        days => plural(days / 365, "year"),
+    }
+}
+
+/// Distinct citation numbers such as `[2]` or `[1, 3]` in `reply`, in order of
+/// first appearance, ignoring any outside `1..=max`.
+pub fn parse_citations(reply: &str, max: usize) -> Vec<usize> {
+    static CITATION: OnceLock<Regex> = OnceLock::new();
+    let citation = CITATION.get_or_init(|| Regex::new(r"\[(\d+(?:\s*,\s*\d+)*)\]").expect("valid regex"));
+    
+    let mut numbers = Vec::new();
+    for captures in citation.captures_iter(reply) {
+        for number in captures[1].split(',').filter_map(|n| n.trim().parse::<usize>().ok()) {
+            if (1..=max).contains(&number) && !numbers.contains(&number) {
+                numbers.push(number);
+            }
+        }
+    }
+    numbers
 }
 
 pub fn build_messages(
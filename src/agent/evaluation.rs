@@ -0,0 +1,177 @@
+use crate::agent::rewrite::QueryExpansion;
+use crate::knowledge::decay::TimeDecay;
+use crate::knowledge::expansion::ContextExpansion;
+use crate::knowledge::interchange::Format;
+use crate::knowledge::rerank::RerankMode;
+use crate::{AssistantError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// A labelled query: the sources a good retrieval should return for it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalCase {
+    pub query: String,
+    /// Expected sources. A hit matches when its source is the same path or
+    /// ends with it, so `notes/rust.md` matches `/home/me/notes/rust.md`.
+    #[serde(alias = "expected")]
+    pub relevant: Vec<String>,
+}
+
+/// Reads labelled queries from a JSON array or a JSONL file, one case per line.
+pub fn read_cases(path: &Path) -> Result<Vec<EvalCase>> {
+    let reader = BufReader::new(File::open(path)?);
+    let cases: Vec<EvalCase> = match Format::from_path(path) {
+        Format::Jsonl => reader
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| line.as_ref().map(|l| !l.trim().is_empty()).unwrap_or(true))
+            .map(|(i, line)| {
+                serde_json::from_str(&line?).map_err(|e| {
+                    AssistantError::SerializationError(format!("line {}: {}", i + 1, e))
+                })
+            })
+            .collect::<Result<_>>()?,
+        Format::Json => serde_json::from_reader(reader)
+            .map_err(|e| AssistantError::SerializationError(e.to_string()))?,
+    };
+    if cases.is_empty() {
+        return Err(AssistantError::KnowledgeError(format!("no queries in {}", path.display())));
+    }
+    Ok(cases)
+}
+
+/// Retrieval settings an evaluation ran with, so reports from different
+/// runs can be told apart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalConfig {
+    pub embedding_model: String,
+    pub chunk_size: usize,
+    pub chunk_overlap: usize,
+    pub min_similarity: f32,
+    pub mmr_lambda: f32,
+    pub fetch_k: usize,
+    pub rerank_mode: RerankMode,
+    pub query_expansion: QueryExpansion,
+    pub collections: Vec<String>,
+    /// The search filter in `filter` syntax, when one was set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// Time decay of each searched collection that had it on.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub time_decay: BTreeMap<String, TimeDecay>,
+    pub context_expansion: ContextExpansion,
+    pub context_window: usize,
+    /// Chunks chat retrieves per message; the evaluation itself uses `k`.
+    pub retrieval_k: usize,
+}
+
+/// How one query fared. Sources are ranked by their best chunk; later
+/// chunks of the same source don't count again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryResult {
+    pub query: String,
+    pub relevant: Vec<String>,
+    /// Distinct sources retrieved, best first.
+    pub retrieved: Vec<String>,
+    pub recall: f32,
+    pub reciprocal_rank: f32,
+    pub ndcg: f32,
+}
+
+impl QueryResult {
+    /// Scores `retrieved`, the source of each hit in rank order, against the
+    /// case's expected sources. Each retrieved source counts at most once,
+    /// however many expected sources it matches.
+    pub fn score(case: &EvalCase, retrieved: Vec<String>, k: usize) -> Self {
+        let mut ranked = distinct_sources(retrieved);
+        ranked.truncate(k);
+
+        let relevant = distinct_sources(case.relevant.iter().cloned());
+        // Ranks (0-based) at which expected sources were first found
+        let found: BTreeSet<usize> = relevant
+            .iter()
+            .filter_map(|expected| ranked.iter().position(|source| source_matches(source, expected)))
+            .collect();
+        let relevant = relevant.len();
+
+        let recall = if relevant == 0 { 0.0 } else { found.len() as f32 / relevant as f32 };
+        let reciprocal_rank = found.first().map_or(0.0, |&rank| 1.0 / (rank + 1) as f32);
+        let dcg: f32 = found.iter().map(|&rank| discount(rank)).sum();
+        let ideal: f32 = (0..relevant.min(k)).map(discount).sum();
+        let ndcg = if ideal > 0.0 { dcg / ideal } else { 0.0 };
+
+        Self {
+            query: case.query.clone(),
+            relevant: case.relevant.clone(),
+            retrieved: ranked,
+            recall,
+            reciprocal_rank,
+            ndcg,
+        }
+    }
+}
+
+/// Mean metrics over a labelled query set, plus the per-query detail.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalReport {
+    pub run_at: DateTime<Utc>,
+    pub k: usize,
+    pub config: EvalConfig,
+    pub recall_at_k: f32,
+    pub mrr: f32,
+    pub ndcg_at_k: f32,
+    pub queries: Vec<QueryResult>,
+}
+
+impl EvalReport {
+    pub fn new(k: usize, config: EvalConfig, queries: Vec<QueryResult>) -> Self {
+        let mean = |metric: fn(&QueryResult) -> f32| {
+            if queries.is_empty() {
+                0.0
+            } else {
+                queries.iter().map(metric).sum::<f32>() / queries.len() as f32
+            }
+        };
+        Self {
+            run_at: Utc::now(),
+            k,
+            config,
+            recall_at_k: mean(|q| q.recall),
+            mrr: mean(|q| q.reciprocal_rank),
+            ndcg_at_k: mean(|q| q.ndcg),
+            queries,
+        }
+    }
+
+    /// Writes the report as pretty-printed JSON, stable enough to diff.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| AssistantError::SerializationError(e.to_string()))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// `sources` without repeats, in first-seen order.
+pub fn distinct_sources(sources: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut distinct: Vec<String> = Vec::new();
+    for source in sources {
+        if !distinct.contains(&source) {
+            distinct.push(source);
+        }
+    }
+    distinct
+}
+
+fn source_matches(source: &str, expected: &str) -> bool {
+    source == expected || Path::new(source).ends_with(expected)
+}
+
+/// DCG weight of a relevant hit at 0-based `rank`, with binary relevance.
+fn discount(rank: usize) -> f32 {
+    1.0 / ((rank + 2) as f32).log2()
+}
@@ -1,3 +1,5 @@
 pub mod assistant;
 pub mod chain;
+pub mod evaluation;
 pub mod personality;
+pub mod rewrite;
@@ -13,7 +13,19 @@ pub struct PersonalityProfile {
     pub user_preferences: UserPreferences,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Retrieved material for one chat turn; each section of the system prompt
+/// is left out when its part is `None`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PromptContext<'a> {
+    /// Numbered knowledge chunks the reply may cite.
+    pub knowledge: Option<&'a str>,
+    /// Relevant past exchanges with their dates.
+    pub episodes: Option<&'a str>,
+    /// Facts from the knowledge graph about entities in the message.
+    pub graph: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct UserPreferences {
     pub name: Option<String>,
     pub preferred_topics: Vec<String>,
@@ -46,17 +58,6 @@ impl Default for PersonalityProfile {
     }
 }
 
-impl Default for UserPreferences {
-    fn default() -> Self {
-        Self {
-            name: None,
-            preferred_topics: Vec::new(),
-            communication_preferences: Vec::new(),
-            relationship_goals: Vec::new(),
-        }
-    }
-}
-
 impl PersonalityProfile {
     pub fn load_or_create(data_dir: &Path) -> Result<Self> {
         let profile_path = data_dir.join("personality.json");
@@ -99,7 +100,8 @@ impl PersonalityProfile {
         }
     }
 
-    pub fn build_system_prompt(&self, knowledge_context: &str) -> String {
+    /// Builds the system prompt around whatever `context` was retrieved.
+    pub fn build_system_prompt(&self, context: PromptContext) -> String {
         let user_name = self.user_preferences.name
             .as_ref()
             .map(|n| format!("(User's name is {})", n))
@@ -117,14 +119,34 @@ impl PersonalityProfile {
             String::new()
         };
 
+        let graph = context.graph
+            .map(|facts| format!("\n\nKnown facts about things the user mentioned:\n{}", facts))
+            .unwrap_or_default();
+
+        let knowledge = context.knowledge
+            .map(|context| format!(
+                "\n\nKnowledge base context (numbered entries):\n{}\n\n\
+                When you use a knowledge entry, cite it inline with its number, like [1] or [2, 3]. \
+                Don't cite entries you didn't use. When entries disagree, prefer the more recently learned one.",
+                context
+            ))
+            .unwrap_or_default();
+
+        let past_conversations = context.episodes
+            .map(|episodes| format!(
+                "\n\nEarlier conversations with the user that may be relevant:\n{}\n\n\
+                Refer back to them naturally when it helps, using their dates (e.g. \"last Tuesday you mentioned...\").",
+                episodes
+            ))
+            .unwrap_or_default();
+
         format!(
             "You are {}, an AI companion with the following personality:\n\
             - {}\n\
             - Communication style: {}\n\
             - Your interests: {}\n\
             - Relationship context: {}\n\
-            {}{}{}\n\n\
-            Knowledge base context:\n{}\n\n\
+            {}{}{}{}{}{}\n\n\
             Be authentic, caring, and remember our relationship grows over time. \
             Show genuine interest in the user's life and feelings. Use emojis occasionally \
             to express emotions, but don't overdo it. Be supportive and encouraging.",
@@ -136,7 +158,9 @@ impl PersonalityProfile {
             user_name,
             memories,
             interests,
-            knowledge_context
+            past_conversations,
+            graph,
+            knowledge
         )
     }
 }
\ No newline at end of file
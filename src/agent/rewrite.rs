@@ -0,0 +1,179 @@
+use crate::llm::ollama::OllamaClient;
+use crate::llm::types::Message;
+use crate::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Messages of history shown to the model when condensing a follow-up.
+const HISTORY_WINDOW: usize = 6;
+/// Characters kept from each history message in the condense prompt.
+const HISTORY_MESSAGE_CHARS: usize = 400;
+
+/// Extra queries generated alongside the standalone one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryExpansion {
+    Off,
+    /// Several differently worded queries for the same need.
+    Multi,
+    /// A hypothetical answer, searched for as if it were a document (HyDE).
+    Hyde,
+}
+
+impl QueryExpansion {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "off" | "none" | "" => Some(Self::Off),
+            "multi" | "multi-query" | "variants" => Some(Self::Multi),
+            "hyde" => Some(Self::Hyde),
+            _ => None,
+        }
+    }
+}
+
+/// The queries retrieval runs for one chat message. The first is the
+/// standalone question; any others are expansions to fuse with it.
+#[derive(Debug, Clone, Default)]
+pub struct RewrittenQuery {
+    pub queries: Vec<String>,
+    /// Set when a rewriting step failed and was skipped.
+    pub error: Option<String>,
+}
+
+/// Turns a chat message into retrieval queries using recent history.
+pub struct QueryRewriter {
+    ollama: OllamaClient,
+    model: String,
+    condense: bool,
+    expansion: QueryExpansion,
+    variants: usize,
+}
+
+impl QueryRewriter {
+    pub fn new(ollama: OllamaClient, model: String, condense: bool, expansion: QueryExpansion, variants: usize) -> Self {
+        Self { ollama, model, condense, expansion, variants }
+    }
+
+    /// Condenses `history` and `message` into a standalone query, then adds
+    /// expansions. A failing step is skipped, so the raw message is always
+    /// searched at worst.
+    pub async fn rewrite(&self, history: &[Message], message: &str) -> RewrittenQuery {
+        let mut rewritten = RewrittenQuery::default();
+
+        let standalone = if self.condense && !history.is_empty() {
+            match self.condense(history, message).await {
+                Ok(query) => query,
+                Err(e) => {
+                    rewritten.error = Some(e.to_string());
+                    message.to_string()
+                }
+            }
+        } else {
+            message.to_string()
+        };
+
+        let expansions = match self.expansion {
+            QueryExpansion::Off => Ok(Vec::new()),
+            QueryExpansion::Multi => self.variants(&standalone).await,
+            QueryExpansion::Hyde => self.hypothetical_answer(&standalone).await.map(|answer| vec![answer]),
+        };
+        rewritten.queries.push(standalone);
+        match expansions {
+            Ok(expansions) => {
+                for query in expansions {
+                    if !rewritten.queries.contains(&query) {
+                        rewritten.queries.push(query);
+                    }
+                }
+            }
+            Err(e) => rewritten.error = Some(e.to_string()),
+        }
+        rewritten
+    }
+
+    async fn condense(&self, history: &[Message], message: &str) -> Result<String> {
+        let transcript = history
+            .iter()
+            .rev()
+            .take(HISTORY_WINDOW)
+            .rev()
+            .map(|m| format!("{}: {}", m.role, m.content.chars().take(HISTORY_MESSAGE_CHARS).collect::<String>()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let prompt = format!(
+            "Rewrite the user's last message as a standalone search query that can be \
+            understood without the conversation. Resolve pronouns and references such as \
+            \"it\" or \"the second one\" using the conversation. If it is already standalone, \
+            repeat it unchanged. Reply with the query only.\n\n\
+            Conversation:\n{}\n\nLast message: {}\n\nStandalone query:",
+            transcript, message
+        );
+        let reply = self.ollama.generate(&self.model, &prompt, false, 0.0).await?;
+        Ok(clean_line(&reply).unwrap_or_else(|| message.to_string()))
+    }
+
+    async fn variants(&self, query: &str) -> Result<Vec<String>> {
+        let prompt = format!(
+            "Write {} different search queries that would find information answering the \
+            question below. Vary the wording and the terms used. One query per line, no \
+            numbering or commentary.\n\nQuestion: {}\n\nQueries:",
+            self.variants, query
+        );
+        let reply = self.ollama.generate(&self.model, &prompt, false, 0.3).await?;
+        Ok(reply.lines().filter_map(clean_line).take(self.variants).collect())
+    }
+
+    async fn hypothetical_answer(&self, query: &str) -> Result<String> {
+        let prompt = format!(
+            "Write a short, factual passage (3-4 sentences) that answers the question below, \
+            as it might appear in a reference document. It does not need to be correct.\n\n\
+            Question: {}\n\nPassage:",
+            query
+        );
+        let reply = self.ollama.generate(&self.model, &prompt, false, 0.3).await?;
+        Ok(reply.trim().to_string())
+    }
+}
+
+/// First non-empty line of `text` with list markers and quotes stripped.
+fn clean_line(text: &str) -> Option<String> {
+    static MARKER: OnceLock<Regex> = OnceLock::new();
+    let marker = MARKER.get_or_init(|| Regex::new(r"^(?:\d+[.)]|[-*•])(?:\s+|$)").expect("valid regex"));
+    text.lines()
+        .map(|line| marker.replace(line.trim(), "").trim().trim_matches('"').trim().to_string())
+        .find(|line| !line.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_line_strips_numbering_and_bullets() {
+        assert_eq!(clean_line("1. rust ownership rules").as_deref(), Some("rust ownership rules"));
+        assert_eq!(clean_line("2) borrow checker").as_deref(), Some("borrow checker"));
+        assert_eq!(clean_line("- lifetimes").as_deref(), Some("lifetimes"));
+        assert_eq!(clean_line("* move semantics").as_deref(), Some("move semantics"));
+        assert_eq!(clean_line("• smart pointers").as_deref(), Some("smart pointers"));
+    }
+
+    #[test]
+    fn clean_line_keeps_numbers_that_are_not_markers() {
+        assert_eq!(clean_line("2024 tax deadline").as_deref(), Some("2024 tax deadline"));
+        assert_eq!(clean_line("-flag usage").as_deref(), Some("-flag usage"));
+    }
+
+    #[test]
+    fn clean_line_strips_quotes() {
+        assert_eq!(clean_line("\"when was the invoice sent\"").as_deref(), Some("when was the invoice sent"));
+        assert_eq!(clean_line("1. \"billing service owner\"").as_deref(), Some("billing service owner"));
+    }
+
+    #[test]
+    fn clean_line_skips_blank_lines() {
+        assert_eq!(clean_line("\n   \n  -  \n  \"\"\n  first query\nsecond").as_deref(), Some("first query"));
+        assert_eq!(clean_line(" \n\n"), None);
+        assert_eq!(clean_line(""), None);
+    }
+}
@@ -1,15 +1,27 @@
-use crate::agent::assistant::Assistant;
+use crate::agent::assistant::{Assistant, FileIngestResult, FileOutcome};
+use crate::agent::chain::{format_age, source_label};
+use crate::agent::evaluation::{EvalReport, read_cases};
+use crate::knowledge::filter::SearchFilter;
+use crate::knowledge::interchange::ConflictPolicy;
+use crate::knowledge::quantization::Quantization;
 use colored::*;
-use std::io::{self, Write};
+use dialoguer::Confirm;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
 use anyhow::Result;
+use tokio::sync::mpsc;
 
 pub struct UI {
     assistant: Assistant,
+    /// Set when a background re-embed batch fails; cleared by 'reembed'.
+    reembed_paused: bool,
 }
 
 impl UI {
     pub fn new(assistant: Assistant) -> Self {
-        Self { assistant }
+        Self { assistant, reembed_paused: false }
     }
     
     pub async fn run_interactive(mut self) -> Result<()> {
@@ -30,8 +42,32 @@ impl UI {
         
         println!("\n{}", "💬 Commands:".bold());
         println!("  • Just chat with me naturally!");
-        println!("  • 'learn: <text>' - Teach me something new");
+        println!("  • 'learn: <text>' - Teach me something new ('learn: @work <text>' to pick a collection)");
         println!("  • 'file: <path>' - Let me learn from a file");
+        println!("  • 'dir: <path>' - Let me learn from every text file in a folder");
+        println!("  • 'watch: <path>' - Keep me in sync with a folder while we chat");
+        println!("  • 'unwatch: <path>' - Stop watching a folder");
+        println!("  • 'watch' - See which folders I'm keeping in sync");
+        println!("  • 'collections' - List knowledge collections");
+        println!("  • 'new-collection: <name>' / 'drop-collection: <name>' - Create or delete a collection");
+        println!("  • 'into: <name>' - Learn into a collection from now on");
+        println!("  • 'search-in: <name,name|all>' - Choose which collections I draw on when we chat");
+        println!("  • 'decay: [@collection] <days|off|default>' - Rank newer knowledge higher, halving the boost every <days>");
+        println!("  • 'tag: <source> #tag key=value' - Tag what I learned from a file or folder");
+        println!("  • 'scope: <filter>' - Only use matching knowledge, e.g. 'scope: tag:work' ('scope: off' to reset)");
+        println!("  • 'entities' / 'entities: <text>' - List the people, services and things I know about");
+        println!("  • 'entity: <name>' - Show what I know relates to something");
+        println!("  • 'sources' / 'sources: <text>' - List what I learned from, with chunk counts and dates");
+        println!("  • 'show: <source>' - Show the chunks I keep from a source");
+        println!("  • 'search: <query>' - Raw vector search with similarity scores");
+        println!("  • 'stats' - Knowledge store size on disk, embedding model and dimension");
+        println!("  • 'forget: <source>' - Forget everything I learned from a file or 'cli'");
+        println!("  • 'forget-id: <id>' - Forget one piece of knowledge");
+        println!("  • 'update: <id> <text>' - Correct one piece of knowledge");
+        println!("  • 'export: <file.json|file.jsonl> [--no-embeddings]' - Export the current collection");
+        println!("  • 'import: <file> [--replace]' - Import into the current collection, skipping (or replacing) what I already know");
+        println!("  • 'eval: <queries.jsonl> [--k N] [--json report.json]' - Score retrieval against labelled queries");
+        println!("  • 'reembed' - Migrate my knowledge to the configured embedding model");
         println!("  • 'name: <your name>' - Tell Ipsi your name");
         println!("  • 'interest: <topic>' - Share your interests with Ipsi");
         println!("  • 'save' - Save our conversation");
@@ -40,12 +76,52 @@ impl UI {
         println!("  • 'quit' - Say goodbye (Ipsi will remember you!)");
         println!("{}\n", "─".repeat(60));
         
+        if !self.assistant.watch_status().folders.is_empty() {
+            println!("{} Catching up on your watched folders...", "🔄".blue());
+            let progress = ingest_progress();
+            let started = Instant::now();
+            let synced = self.assistant.resync_watched(&progress).await;
+            progress.finish_and_clear();
+            match synced {
+                Ok(results) => print_ingest_summary(&results, started.elapsed()),
+                Err(e) => println!("{} {}", "❌".red(), e),
+            }
+            println!();
+        }
+        
+        let status = self.assistant.embedding_status();
+        if status.is_mismatched() && status.stale > 0 {
+            println!(
+                "{} {} of my {} knowledge items were embedded with {} but {} is configured now.",
+                "⚠️".yellow(), status.stale, status.total, status.store_model.bold(), status.configured_model.bold()
+            );
+            println!("   I'll ignore them until you run {} to migrate them.\n", "'reembed'".bold());
+        } else if status.is_migrating() {
+            println!("{} Resuming re-embedding in the background ({} items left)\n", "🔁".blue(), status.stale);
+        }
+        
+        let mut lines = StdinLines::spawn();
+        let mut sync_tick = tokio::time::interval(Duration::from_millis(500));
+        let mut prompt = true;
+        
         loop {
-            print!("{} ", "You:".green().bold());
-            io::stdout().flush()?;
+            if prompt {
+                print!("{} ", "You:".green().bold());
+                io::stdout().flush()?;
+            }
+            prompt = true;
             
-            let mut input = String::new();
-            io::stdin().read_line(&mut input)?;
+            // Watched-folder changes are applied between commands
+            let input = tokio::select! {
+                line = lines.next_line() => match line {
+                    Some(line) => line,
+                    None => break,
+                },
+                _ = sync_tick.tick() => {
+                    prompt = self.background_tick().await;
+                    continue;
+                }
+            };
             let input = input.trim();
             
             if input.is_empty() {
@@ -76,10 +152,151 @@ impl UI {
                     }
                     println!("  💬 Messages exchanged: {}", info.conversation_count.to_string().bold());
                     println!("  🧠 Knowledge items: {}", info.knowledge_count.to_string().bold());
+                    println!(
+                        "  🗂️ Learning into: {}, drawing on: {}",
+                        info.learn_target.bold(),
+                        info.searched_collections.join(", ")
+                    );
                     println!("  💭 Shared memories: {}", info.memories_count.to_string().bold());
+                    if let Some(episodes) = info.episodes_count {
+                        println!("  📖 Past exchanges remembered: {}", episodes.to_string().bold());
+                    }
+                    if let Some(graph) = &info.graph {
+                        println!(
+                            "  🕸️ Knowledge graph: {} entities, {} relations",
+                            graph.entities.to_string().bold(),
+                            graph.relations.to_string().bold()
+                        );
+                    }
                     println!("  🤖 AI Model: {}", info.model);
+                    let embeddings = &info.embedding_status;
+                    println!(
+                        "  🧬 Embeddings: {} ({} dims)",
+                        embeddings.store_model,
+                        embeddings.dimension.map(|d| d.to_string()).unwrap_or_else(|| "?".to_string())
+                    );
+                    if embeddings.stale > 0 {
+                        println!("     {} {} items not yet embedded with {}", "⚠".yellow(), embeddings.stale, embeddings.configured_model);
+                    }
+                    let memory = &info.vector_memory;
+                    if memory.quantization != Quantization::None {
+                        println!(
+                            "  🗜️ Vectors: {:?}, {} in memory instead of {} ({} saved){}",
+                            memory.quantization,
+                            format_bytes(memory.in_memory_bytes),
+                            format_bytes(memory.full_precision_bytes),
+                            format_bytes(memory.saved_bytes()),
+                            if memory.full_on_disk { ", full precision on disk for rescoring" } else { "" }
+                        );
+                    }
+                    if let Some(cache) = &info.embedding_cache {
+                        println!(
+                            "  💾 Embedding cache: {} vectors, {} hits / {} lookups this session ({:.0}% hit rate)",
+                            cache.entries,
+                            cache.hits,
+                            cache.hits + cache.misses,
+                            cache.hit_rate() * 100.0
+                        );
+                    }
+                    if let Some(rewritten) = &info.last_queries {
+                        println!("  🔍 Last search queries:");
+                        for query in &rewritten.queries {
+                            println!("     • {}", query.replace('\n', " ").chars().take(120).collect::<String>());
+                        }
+                        if let Some(error) = &rewritten.error {
+                            println!("     {} Rewriting failed: {}", "⚠".yellow(), error);
+                        }
+                    }
+                    if let Some(rerank) = &info.last_rerank {
+                        println!(
+                            "  ⏱️ Last rerank ({:?}): {} candidates in {} ms{}",
+                            rerank.mode,
+                            rerank.candidates,
+                            rerank.elapsed.as_millis(),
+                            rerank.error.as_ref().map(|e| format!(" (failed: {})", e)).unwrap_or_default()
+                        );
+                    }
                     println!();
                 }
+                "reembed" => {
+                    let status = self.assistant.embedding_status();
+                    if status.is_migrating() && self.reembed_paused {
+                        self.reembed_paused = false;
+                        println!("{} Resuming re-embedding ({} items left) 🔁\n", "✅".green(), status.stale);
+                    } else if status.is_migrating() {
+                        println!(
+                            "{} Re-embedding with {}: {}/{} done\n",
+                            "🔁".blue(), status.configured_model, status.total - status.stale, status.total
+                        );
+                    } else if !status.is_mismatched() && !confirm(&format!(
+                        "All {} items already use {}. Re-embed them anyway?", status.total, status.configured_model
+                    )) {
+                        println!("{} Nothing to do\n", "↩".dimmed());
+                    } else {
+                        match self.assistant.start_reembed() {
+                            Ok(status) => println!(
+                                "{} Re-embedding {} items with {} in the background - keep chatting! 🔁\n",
+                                "✅".green(), status.stale, status.configured_model
+                            ),
+                            Err(e) => println!("{} {}\n", "❌".red(), e),
+                        }
+                    }
+                }
+                _ if input.starts_with("export:") => {
+                    let (path, include_embeddings) = match input[7..].trim().strip_suffix("--no-embeddings") {
+                        Some(path) => (path.trim(), false),
+                        None => (input[7..].trim(), true),
+                    };
+                    match self.assistant.export_knowledge(path, include_embeddings).await {
+                        Ok(n) => println!("{} Exported {} pieces of knowledge to {} 📦\n", "✅".green(), n, path),
+                        Err(e) => println!("{} {}\n", "❌".red(), e),
+                    }
+                }
+                _ if input.starts_with("import:") => {
+                    let (path, policy) = match input[7..].trim().strip_suffix("--replace") {
+                        Some(path) => (path.trim(), ConflictPolicy::Replace),
+                        None => (input[7..].trim(), ConflictPolicy::Skip),
+                    };
+                    print!("{} ", "📥 Importing...".dimmed());
+                    io::stdout().flush()?;
+                    match self.assistant.import_knowledge(path, policy).await {
+                        Ok(report) => println!(
+                            "\r{} Imported {} new, replaced {}, skipped {} already known ({} re-embedded) 📦\n",
+                            "✅".green(), report.imported, report.replaced, report.skipped, report.reembedded
+                        ),
+                        Err(e) => println!("\r{} {}\n", "❌".red(), e),
+                    }
+                }
+                _ if input.starts_with("eval:") => {
+                    let (path, k, report_path) = match parse_eval_args(&input[5..]) {
+                        Ok(args) => args,
+                        Err(e) => {
+                            println!("{} {}\n", "❌".red(), e);
+                            continue;
+                        }
+                    };
+                    let k = k.unwrap_or(self.assistant.retrieval_k());
+                    let cases = match read_cases(Path::new(path)) {
+                        Ok(cases) => cases,
+                        Err(e) => {
+                            println!("{} {}\n", "❌".red(), e);
+                            continue;
+                        }
+                    };
+                    println!("{} Evaluating {} queries at k={}...", "📏".blue(), cases.len(), k);
+                    match self.assistant.evaluate(&cases, k).await {
+                        Ok(report) => {
+                            print_eval_report(&report);
+                            if let Some(report_path) = report_path {
+                                match report.save(Path::new(report_path)) {
+                                    Ok(()) => println!("{} Saved report to {}\n", "✅".green(), report_path),
+                                    Err(e) => println!("{} {}\n", "❌".red(), e),
+                                }
+                            }
+                        }
+                        Err(e) => println!("{} {}\n", "❌".red(), e),
+                    }
+                }
                 _ if input.starts_with("name:") => {
                     let name = input[5..].trim().to_string();
                     if !name.is_empty() {
@@ -99,16 +316,408 @@ impl UI {
                     }
                 }
                 _ if input.starts_with("learn:") => {
-                    let text = &input[6..].trim();
-                    match self.assistant.learn_text(text, "cli").await {
+                    let (collection, text) = self.split_collection(&input[6..]);
+                    let progress = ingest_progress();
+                    let learned = self.assistant.learn_text_in(&collection, text, "cli", &progress).await;
+                    progress.finish_and_clear();
+                    match learned {
+                        Ok(0) => println!("{} I already knew that one! 📚\n", "✅".green()),
                         Ok(_) => println!("{} Thanks for teaching me something new! 📚\n", "✅".green()),
                         Err(e) => println!("{} {}\n", "❌".red(), e),
                     }
                 }
                 _ if input.starts_with("file:") => {
-                    let filepath = &input[5..].trim();
-                    match self.assistant.learn_file(filepath).await {
-                        Ok(_) => println!("{} I've learned so much from {}! Thank you 📖\n", "✅".green(), filepath),
+                    let (collection, filepath) = self.split_collection(&input[5..]);
+                    let progress = ingest_progress();
+                    let learned = self.assistant.learn_file_in(&collection, filepath, &progress).await;
+                    progress.finish_and_clear();
+                    match learned {
+                        Ok(report) if report.is_noop() => println!("{} {} hasn't changed since I last read it 📖\n", "✅".green(), filepath),
+                        Ok(report) => println!(
+                            "{} I've learned so much from {}! Thank you 📖 ({} new, {} removed, {} unchanged)\n",
+                            "✅".green(), filepath, report.added, report.removed, report.unchanged
+                        ),
+                        Err(e) => println!("{} {}\n", "❌".red(), e),
+                    }
+                }
+                "watch" => {
+                    let status = self.assistant.watch_status();
+                    println!("\n{} {}", "👀".blue(), "Watched Folders:".bold());
+                    if status.folders.is_empty() {
+                        println!("  (none yet - try 'watch: <path>')");
+                    }
+                    for folder in &status.folders {
+                        let last_sync = folder.last_sync
+                            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                            .unwrap_or_else(|| "never".to_string());
+                        println!(
+                            "  📁 {} → {} - {} files, last synced {}",
+                            folder.path.display().to_string().bold(), folder.collection, folder.files_synced, last_sync
+                        );
+                        if let Some(error) = &folder.last_error {
+                            println!("     {} {}", "⚠".yellow(), error);
+                        }
+                    }
+                    if !status.active {
+                        println!("  {} Live watching is unavailable; folders sync at startup only", "⚠".yellow());
+                    }
+                    println!("  ⏳ Pending changes: {}\n", status.pending_changes);
+                }
+                "collections" => {
+                    let learn_target = self.assistant.learn_target().to_string();
+                    let searched = self.assistant.searched_collections();
+                    println!("\n{} {}", "🗂️".blue(), "Knowledge Collections:".bold());
+                    for collection in self.assistant.collections() {
+                        let mut marks = Vec::new();
+                        if collection.name == learn_target {
+                            marks.push("learning".to_string());
+                        }
+                        if searched.contains(&collection.name) {
+                            marks.push("searched".to_string());
+                        }
+                        if let Some(decay) = collection.decay {
+                            marks.push(format!("half-life {} days", decay.half_life_days));
+                        }
+                        println!(
+                            "  📚 {} - {} items from {} sources{}",
+                            collection.name.bold(),
+                            collection.chunks,
+                            collection.sources,
+                            if marks.is_empty() { String::new() } else { format!(" ({})", marks.join(", ")).dimmed().to_string() }
+                        );
+                    }
+                    println!();
+                }
+                _ if input.starts_with("new-collection:") => {
+                    let name = input[15..].trim();
+                    match self.assistant.create_collection(name) {
+                        Ok(true) => println!("{} Created collection {} - use 'into: {}' to fill it 🗂️\n", "✅".green(), name.bold(), name),
+                        Ok(false) => println!("{} Collection {} already exists\n", "❌".red(), name),
+                        Err(e) => println!("{} {}\n", "❌".red(), e),
+                    }
+                }
+                _ if input.starts_with("drop-collection:") => {
+                    let name = input[16..].trim();
+                    let chunks = self.assistant.collections().into_iter().find(|c| c.name == name).map(|c| c.chunks);
+                    match chunks {
+                        None => println!("{} No collection named {}\n", "❌".red(), name),
+                        Some(chunks) if confirm(&format!("Delete collection {} and its {} pieces of knowledge?", name, chunks)) => {
+                            match self.assistant.drop_collection(name) {
+                                Ok(n) => println!("{} Dropped {} ({} pieces of knowledge) 🧹\n", "✅".green(), name, n),
+                                Err(e) => println!("{} {}\n", "❌".red(), e),
+                            }
+                        }
+                        Some(_) => println!("{} Okay, I'll keep it\n", "↩".dimmed()),
+                    }
+                }
+                _ if input.starts_with("into:") => {
+                    let name = input[5..].trim();
+                    match self.assistant.set_learn_target(name) {
+                        Ok(()) => println!("{} I'll learn into {} from now on 🗂️\n", "✅".green(), name.bold()),
+                        Err(e) => println!("{} {}\n", "❌".red(), e),
+                    }
+                }
+                _ if input.starts_with("search-in:") => {
+                    let names: Vec<String> = input[10..]
+                        .split(',')
+                        .map(|n| n.trim().to_string())
+                        .filter(|n| !n.is_empty() && n != "all")
+                        .collect();
+                    match self.assistant.set_search_collections(names) {
+                        Ok(()) => println!(
+                            "{} I'll draw on: {} 🔎\n",
+                            "✅".green(), self.assistant.searched_collections().join(", ").bold()
+                        ),
+                        Err(e) => println!("{} {}\n", "❌".red(), e),
+                    }
+                }
+                "scope" => {
+                    let filter = self.assistant.search_filter();
+                    if filter.is_empty() {
+                        println!("{} I'm using everything I know\n", "🔎".blue());
+                    } else {
+                        println!("{} I'm only using knowledge matching: {}\n", "🔎".blue(), filter.to_string().bold());
+                    }
+                }
+                _ if input.starts_with("scope:") => {
+                    let expr = input[6..].trim();
+                    if expr.eq_ignore_ascii_case("off") || expr.is_empty() {
+                        self.assistant.set_search_filter(SearchFilter::default());
+                        println!("{} Back to using everything I know 🔎\n", "✅".green());
+                    } else {
+                        match SearchFilter::parse(expr) {
+                            Ok(filter) => {
+                                println!("{} I'll only use knowledge matching: {} 🔎\n", "✅".green(), filter.to_string().bold());
+                                self.assistant.set_search_filter(filter);
+                            }
+                            Err(e) => println!("{} {}\n", "❌".red(), e),
+                        }
+                    }
+                }
+                _ if input.starts_with("decay:") => {
+                    let (collection, value) = self.split_collection(&input[6..]);
+                    let half_life = match value.to_lowercase().as_str() {
+                        "default" | "" => None,
+                        "off" | "none" => Some(0.0),
+                        days => match days.trim_end_matches('d').trim().parse::<f32>() {
+                            Ok(days) if days >= 0.0 => Some(days),
+                            _ => {
+                                println!("{} Expected a number of days, 'off' or 'default', got '{}'\n", "❌".red(), value);
+                                continue;
+                            }
+                        },
+                    };
+                    match self.assistant.set_half_life(&collection, half_life) {
+                        Ok(Some(decay)) => println!(
+                            "{} Newer knowledge in {} now ranks higher (half-life {} days) ⏳\n",
+                            "✅".green(), collection.bold(), decay.half_life_days
+                        ),
+                        Ok(None) => println!("{} {} is ranked by relevance alone\n", "✅".green(), collection.bold()),
+                        Err(e) => println!("{} {}\n", "❌".red(), e),
+                    }
+                }
+                _ if input.starts_with("tag:") => {
+                    let mut tags = Vec::new();
+                    let mut fields = Vec::new();
+                    let mut source = Vec::new();
+                    for token in input[4..].split_whitespace() {
+                        if let Some(tag) = token.strip_prefix('#') {
+                            tags.push(tag.to_string());
+                        } else if let Some((key, value)) = token.split_once('=') {
+                            fields.push((key.to_string(), value.to_string()));
+                        } else {
+                            source.push(token);
+                        }
+                    }
+                    let source = source.join(" ");
+                    if source.is_empty() || (tags.is_empty() && fields.is_empty()) {
+                        println!("{} Usage: tag: <source> #tag key=value\n", "❌".red());
+                    } else {
+                        match self.assistant.annotate_source(&source, &tags, &fields) {
+                            Ok(0) => println!("{} I haven't learned anything from {} yet\n", "❌".red(), source),
+                            Ok(n) => println!("{} Tagged {} pieces of knowledge from {} 🏷️\n", "✅".green(), n, source),
+                            Err(e) => println!("{} {}\n", "❌".red(), e),
+                        }
+                    }
+                }
+                _ if input.starts_with("entity:") => {
+                    let name = input[7..].trim();
+                    match self.assistant.entity_neighbourhood(name) {
+                        Ok(Some(hood)) => {
+                            let kind = if hood.entity.kind.is_empty() { String::new() } else { format!(" ({})", hood.entity.kind) };
+                            println!("{} {}{}", "🕸️".blue(), hood.entity.name.bold(), kind.dimmed());
+                            for relation in &hood.outgoing {
+                                println!("  → {} {}", relation.predicate, relation.object.bold());
+                            }
+                            for relation in &hood.incoming {
+                                println!("  ← {} {}", relation.subject.bold(), relation.predicate);
+                            }
+                            if hood.outgoing.is_empty() && hood.incoming.is_empty() {
+                                println!("  {}", "No relations yet".dimmed());
+                            }
+                            println!("  {}\n", format!("Mentioned in: {}", hood.entity.sources.join(", ")).dimmed());
+                        }
+                        Ok(None) => match self.assistant.search_entities(name, 5) {
+                            Ok(similar) if !similar.is_empty() => {
+                                let names: Vec<String> = similar.into_iter().map(|(e, _)| e.name).collect();
+                                println!("{} I don't know '{}'. Did you mean: {}?\n", "❓".yellow(), name, names.join(", "));
+                            }
+                            Ok(_) => println!("{} I don't know anything called '{}'\n", "❓".yellow(), name),
+                            Err(e) => println!("{} {}\n", "❌".red(), e),
+                        },
+                        Err(e) => println!("{} {}\n", "❌".red(), e),
+                    }
+                }
+                _ if input == "entities" || input.starts_with("entities:") => {
+                    let text = input.strip_prefix("entities:").unwrap_or("").trim();
+                    match self.assistant.search_entities(text, 30) {
+                        Ok(entities) if entities.is_empty() => println!("{} No entities found\n", "🕸️".blue()),
+                        Ok(entities) => {
+                            println!("{}", "🕸️ Entities:".bold());
+                            for (entity, relations) in entities {
+                                let kind = if entity.kind.is_empty() { String::new() } else { format!(" ({})", entity.kind) };
+                                println!("  • {}{} {}", entity.name.bold(), kind.dimmed(), format!("- {} relations", relations).dimmed());
+                            }
+                            println!();
+                        }
+                        Err(e) => println!("{} {}\n", "❌".red(), e),
+                    }
+                }
+                _ if input == "sources" || input.starts_with("sources:") => {
+                    let text = input.strip_prefix("sources:").unwrap_or("").trim();
+                    let now = chrono::Utc::now();
+                    let sources: Vec<_> = self.assistant.list_sources()
+                        .into_iter()
+                        .filter(|(_, s)| s.source.contains(text))
+                        .collect();
+                    if sources.is_empty() {
+                        println!("{} No sources found\n", "📚".blue());
+                    } else {
+                        println!("\n{} {}", "📚".blue(), format!("Sources ({}):", sources.len()).bold());
+                        for (collection, summary) in sources {
+                            println!(
+                                "  • {} {} {}",
+                                summary.source.bold(),
+                                format!("@{}", collection).dimmed(),
+                                format!(
+                                    "- {} chunks, learned {} ({})",
+                                    summary.chunks,
+                                    summary.last_learned.format("%Y-%m-%d %H:%M"),
+                                    format_age(summary.last_learned, now)
+                                ).dimmed()
+                            );
+                        }
+                        println!();
+                    }
+                }
+                _ if input.starts_with("show:") => {
+                    let source = input[5..].trim();
+                    let chunks = self.assistant.source_chunks(source);
+                    if chunks.is_empty() {
+                        println!("{} I haven't learned anything from '{}' (see 'sources')\n", "❓".yellow(), source);
+                    } else {
+                        println!("\n{} {}", "📄".blue(), format!("{} - {} chunks:", source, chunks.len()).bold());
+                        for (collection, doc) in chunks {
+                            let location = doc.metadata.location()
+                                .unwrap_or_else(|| format!("chunk {}", doc.metadata.chunk_index.map_or(1, |i| i + 1)));
+                            println!(
+                                "  {} {}",
+                                location.cyan(),
+                                format!("@{} · learned {} · {}", collection, doc.metadata.timestamp.format("%Y-%m-%d"), doc.id).dimmed()
+                            );
+                            println!("    {}", doc.content.replace('\n', " ").chars().take(200).collect::<String>());
+                        }
+                        println!();
+                    }
+                }
+                _ if input.starts_with("search:") => {
+                    let query = input[7..].trim();
+                    match self.assistant.raw_search(query, 10).await {
+                        Ok(hits) if hits.is_empty() => println!("{} No hits\n", "🔍".blue()),
+                        Ok(hits) => {
+                            println!("\n{} {}", "🔍".blue(), format!("Top {} hits:", hits.len()).bold());
+                            for (i, hit) in hits.iter().enumerate() {
+                                println!(
+                                    "  {}. {} {} {}",
+                                    i + 1,
+                                    format!("{:.3}", hit.score).yellow(),
+                                    source_label(&hit.document).cyan(),
+                                    hit.document.id.dimmed()
+                                );
+                                println!("     {}", hit.document.content.replace('\n', " ").chars().take(160).collect::<String>());
+                            }
+                            println!();
+                        }
+                        Err(e) => println!("{} {}\n", "❌".red(), e),
+                    }
+                }
+                "stats" => {
+                    let now = chrono::Utc::now();
+                    println!("\n{} {}", "📊".blue(), "Knowledge Stores:".bold());
+                    for (collection, stats) in self.assistant.store_stats() {
+                        println!(
+                            "  📚 {} - {} chunks from {} sources, {} on disk",
+                            collection.bold(),
+                            stats.chunks,
+                            stats.sources,
+                            format_bytes(stats.bytes_on_disk as usize)
+                        );
+                        println!(
+                            "     🧬 {} ({} dims), vectors held {}",
+                            stats.embedding_model,
+                            stats.dimension.map(|d| d.to_string()).unwrap_or_else(|| "?".to_string()),
+                            if stats.quantization == Quantization::None { "at full precision".to_string() } else { format!("as {:?}", stats.quantization) }
+                        );
+                        if let (Some(oldest), Some(newest)) = (stats.oldest, stats.newest) {
+                            println!(
+                                "     🕰️ Learned between {} and {} ({})",
+                                oldest.format("%Y-%m-%d"),
+                                newest.format("%Y-%m-%d"),
+                                format_age(newest, now)
+                            );
+                        }
+                    }
+                    println!();
+                }
+                _ if input.starts_with("forget:") => {
+                    let source = input[7..].trim();
+                    let count = self.assistant.source_chunk_count(source);
+                    if count == 0 {
+                        println!("{} I haven't learned anything from {}\n", "❌".red(), source);
+                    } else if confirm(&format!("Forget all {} pieces of knowledge from {}?", count, source)) {
+                        match self.assistant.forget_source(source) {
+                            Ok(n) => println!("{} Forgot {} pieces of knowledge from {} 🧹\n", "✅".green(), n, source),
+                            Err(e) => println!("{} {}\n", "❌".red(), e),
+                        }
+                    } else {
+                        println!("{} Okay, I'll keep it\n", "↩".dimmed());
+                    }
+                }
+                _ if input.starts_with("forget-id:") => {
+                    let id = input[10..].trim();
+                    let preview = match self.assistant.get_knowledge(id) {
+                        Some(doc) => doc.content.chars().take(80).collect::<String>(),
+                        None => {
+                            println!("{} No knowledge with id {}\n", "❌".red(), id);
+                            continue;
+                        }
+                    };
+                    if confirm(&format!("Forget \"{}\"?", preview.replace('\n', " "))) {
+                        match self.assistant.forget_knowledge(id) {
+                            Ok(_) => println!("{} Forgotten 🧹\n", "✅".green()),
+                            Err(e) => println!("{} {}\n", "❌".red(), e),
+                        }
+                    } else {
+                        println!("{} Okay, I'll keep it\n", "↩".dimmed());
+                    }
+                }
+                _ if input.starts_with("update:") => {
+                    let rest = input[7..].trim();
+                    match rest.split_once(char::is_whitespace) {
+                        Some((id, text)) if !text.trim().is_empty() => {
+                            match self.assistant.update_knowledge(id, text.trim()).await {
+                                Ok(true) => println!("{} Thanks for correcting me! ✏️\n", "✅".green()),
+                                Ok(false) => println!("{} No knowledge with id {}\n", "❌".red(), id),
+                                Err(e) => println!("{} {}\n", "❌".red(), e),
+                            }
+                        }
+                        _ => println!("{} Usage: update: <id> <new text>\n", "❌".red()),
+                    }
+                }
+                _ if input.starts_with("dir:") => {
+                    let (collection, dir) = self.split_collection(&input[4..]);
+                    let progress = ingest_progress();
+                    let started = Instant::now();
+                    let learned = self.assistant.learn_dir_in(&collection, dir, &progress).await;
+                    progress.finish_and_clear();
+                    match learned {
+                        Ok(results) => {
+                            print_ingest_results(&results);
+                            print_ingest_summary(&results, started.elapsed());
+                            println!();
+                        }
+                        Err(e) => println!("{} {}\n", "❌".red(), e),
+                    }
+                }
+                _ if input.starts_with("watch:") => {
+                    let (collection, dir) = self.split_collection(&input[6..]);
+                    let progress = ingest_progress();
+                    let started = Instant::now();
+                    let synced = self.assistant.watch_folder_in(&collection, dir, &progress).await;
+                    progress.finish_and_clear();
+                    match synced {
+                        Ok(results) => {
+                            print_ingest_summary(&results, started.elapsed());
+                            println!("{} I'll keep an eye on {} for you 👀\n", "✅".green(), dir);
+                        }
+                        Err(e) => println!("{} {}\n", "❌".red(), e),
+                    }
+                }
+                _ if input.starts_with("unwatch:") => {
+                    let dir = &input[8..].trim();
+                    match self.assistant.unwatch_folder(dir) {
+                        Ok(true) => println!("{} Stopped watching {} (I still remember what I read)\n", "✅".green(), dir),
+                        Ok(false) => println!("{} I wasn't watching {}\n", "❌".red(), dir),
                         Err(e) => println!("{} {}\n", "❌".red(), e),
                     }
                 }
@@ -117,10 +726,23 @@ impl UI {
                     io::stdout().flush()?;
                     
                     match self.assistant.chat(input).await {
-                        Ok(response) => {
+                        Ok(reply) => {
                             print!("\r{}\r", " ".repeat(25));
                             let personality_name = self.assistant.get_personality_name();
-                            println!("{} {}\n", format!("💖 {}:", personality_name).magenta().bold(), response);
+                            println!("{} {}\n", format!("💖 {}:", personality_name).magenta().bold(), reply.content);
+                            if !reply.citations.is_empty() {
+                                println!("{}", "📚 Sources:".dimmed());
+                                for citation in &reply.citations {
+                                    let location = citation.location.as_ref()
+                                        .map(|l| format!(" ({})", l))
+                                        .unwrap_or_default();
+                                    println!("  {}", format!("[{}] {}{}", citation.number, citation.source, location).dimmed());
+                                }
+                                println!();
+                            }
+                            if let Some(warning) = &reply.warning {
+                                println!("{} {}\n", "⚠".yellow(), warning.dimmed());
+                            }
                         }
                         Err(e) => {
                             print!("\r{}\r", " ".repeat(25));
@@ -136,3 +758,214 @@ impl UI {
     }
 }
 
+impl UI {
+    /// Splits an optional leading `@collection` off a command argument,
+    /// defaulting to the current learn target.
+    fn split_collection<'a>(&self, arg: &'a str) -> (String, &'a str) {
+        let arg = arg.trim();
+        match arg.strip_prefix('@').map(|rest| rest.split_once(char::is_whitespace).unwrap_or((rest, ""))) {
+            Some((collection, rest)) => (collection.to_string(), rest.trim()),
+            None => (self.assistant.learn_target().to_string(), arg),
+        }
+    }
+    
+    /// Work done while waiting for input: applies watched-folder changes and
+    /// advances any re-embedding migration. Returns whether anything was
+    /// printed, in which case the prompt needs redrawing.
+    async fn background_tick(&mut self) -> bool {
+        let mut printed = false;
+        
+        match self.assistant.sync_watched_changes().await {
+            Ok(results) if results.is_empty() => {}
+            Ok(results) => {
+                println!("\n{} Synced changes in watched folders:", "🔄".blue());
+                print_ingest_results(&results);
+                println!();
+                printed = true;
+            }
+            Err(e) => {
+                println!("\n{} Watch sync failed: {}\n", "❌".red(), e);
+                printed = true;
+            }
+        }
+        
+        if self.reembed_paused {
+            return printed;
+        }
+        match self.assistant.reembed_step().await {
+            Ok(Some(status)) if status.stale == 0 => {
+                println!("\n{} Finished re-embedding {} items with {} 🧬\n", "✅".green(), status.total, status.configured_model);
+                printed = true;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                println!("\n{} Re-embedding paused: {} (run 'reembed' to resume)\n", "❌".red(), e);
+                self.reembed_paused = true;
+                printed = true;
+            }
+        }
+        
+        printed
+    }
+}
+
+/// Reads stdin on a dedicated thread so the interactive loop can keep
+/// servicing background work while waiting for input. A line is only read
+/// when one is asked for, so prompts such as `dialoguer` confirmations can
+/// use the terminal in between.
+struct StdinLines {
+    requests: std::sync::mpsc::Sender<()>,
+    lines: mpsc::UnboundedReceiver<String>,
+    pending: bool,
+}
+
+impl StdinLines {
+    fn spawn() -> Self {
+        let (requests, request_rx) = std::sync::mpsc::channel::<()>();
+        let (tx, lines) = mpsc::unbounded_channel();
+        std::thread::spawn(move || {
+            let stdin = io::stdin();
+            while request_rx.recv().is_ok() {
+                let mut line = String::new();
+                match stdin.lock().read_line(&mut line) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {
+                        if tx.send(line).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        Self { requests, lines, pending: false }
+    }
+    
+    /// Next input line, or `None` once stdin is closed. Cancel-safe.
+    async fn next_line(&mut self) -> Option<String> {
+        if !self.pending {
+            self.requests.send(()).ok()?;
+            self.pending = true;
+        }
+        let line = self.lines.recv().await;
+        self.pending = false;
+        line
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Asks a yes/no question, treating a non-interactive terminal as "no".
+fn confirm(prompt: &str) -> bool {
+    Confirm::new().with_prompt(prompt).default(false).interact().unwrap_or(false)
+}
+
+fn print_ingest_results(results: &[FileIngestResult]) {
+    for result in results {
+        match &result.outcome {
+            FileOutcome::Learned(report) if report.is_noop() => {
+                println!("  {} {} (unchanged)", "•".dimmed(), result.path);
+            }
+            FileOutcome::Learned(report) => {
+                println!("  {} {} ({} new, {} removed)", "✓".green(), result.path, report.added, report.removed);
+            }
+            FileOutcome::Skipped(reason) => println!("  {} {} ({})", "-".yellow(), result.path, reason),
+            FileOutcome::Failed(e) => println!("  {} {} ({})", "✗".red(), result.path, e),
+            FileOutcome::Removed(n) => println!("  {} {} (forgot {} chunks)", "✗".yellow(), result.path, n),
+        }
+    }
+}
+
+fn print_ingest_summary(results: &[FileIngestResult], elapsed: Duration) {
+    let count = |f: fn(&FileOutcome) -> bool| results.iter().filter(|r| f(&r.outcome)).count();
+    let chunks: usize = results
+        .iter()
+        .map(|r| match &r.outcome {
+            FileOutcome::Learned(report) => report.added,
+            _ => 0,
+        })
+        .sum();
+    println!(
+        "{} Read {} files, {} new chunks ({} skipped, {} failed, {} removed) in {:.1}s 📖",
+        "✅".green(),
+        count(|o| matches!(o, FileOutcome::Learned(_))),
+        chunks,
+        count(|o| matches!(o, FileOutcome::Skipped(_))),
+        count(|o| matches!(o, FileOutcome::Failed(_))),
+        count(|o| matches!(o, FileOutcome::Removed(_))),
+        elapsed.as_secs_f32(),
+    );
+}
+
+/// Splits `<path> [--k N] [--json report.json]`.
+fn parse_eval_args(arg: &str) -> Result<(&str, Option<usize>, Option<&str>)> {
+    let mut path = arg.trim();
+    let mut k = None;
+    let mut report = None;
+    loop {
+        if let Some((rest, value)) = path.rsplit_once(" --json ") {
+            report = Some(value.trim());
+            path = rest.trim();
+        } else if let Some((rest, value)) = path.rsplit_once(" --k ") {
+            let value = value.trim();
+            k = Some(value.parse().map_err(|_| anyhow::anyhow!("--k must be a number, got '{}'", value))?);
+            path = rest.trim();
+        } else {
+            break;
+        }
+    }
+    if path.is_empty() {
+        return Err(anyhow::anyhow!("Usage: eval: <queries.jsonl> [--k N] [--json report.json]"));
+    }
+    Ok((path, k, report))
+}
+
+fn print_eval_report(report: &EvalReport) {
+    for query in &report.queries {
+        let mark = if query.recall >= 1.0 {
+            "✓".green()
+        } else if query.recall > 0.0 {
+            "~".yellow()
+        } else {
+            "✗".red()
+        };
+        println!(
+            "  {} {} {}",
+            mark,
+            query.query.chars().take(60).collect::<String>(),
+            format!("(recall {:.2}, RR {:.2}, nDCG {:.2})", query.recall, query.reciprocal_rank, query.ndcg).dimmed()
+        );
+    }
+    println!(
+        "{} recall@{}: {:.3}  MRR: {:.3}  nDCG@{}: {:.3}\n",
+        "📊".blue(),
+        report.k,
+        report.recall_at_k,
+        report.mrr,
+        report.k,
+        report.ndcg_at_k
+    );
+}
+
+/// Progress bar for learning commands, showing throughput and time left.
+/// The phase goes in the prefix and the unit being counted in the message.
+fn ingest_progress() -> ProgressBar {
+    let style = ProgressStyle::with_template(
+        "{prefix:>9.cyan} [{bar:30.cyan/blue}] {pos}/{len} {msg} ({per_sec}, ETA {eta})",
+    )
+    .expect("valid progress template")
+    .progress_chars("=> ");
+    ProgressBar::new(0).with_style(style)
+}
@@ -1,12 +1,21 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use anyhow::Result;
+use crate::agent::rewrite::QueryExpansion;
+use crate::knowledge::embeddings::EmbeddingBackend;
+use crate::knowledge::expansion::ContextExpansion;
+use crate::knowledge::hashing;
+use crate::knowledge::quantization::Quantization;
+use crate::knowledge::rerank::RerankMode;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
     pub ollama_host: String,
     pub ollama_model: String,
     pub embedding_model: String,
+    pub embedding_backend: EmbeddingBackend,
+    pub local_embedding_dim: usize,
+    pub embedding_cache: bool,
     pub temperature: f32,
     pub data_dir: PathBuf,
     pub knowledge_dir: PathBuf,
@@ -15,6 +24,32 @@ pub struct Settings {
     pub chunk_overlap: usize,
     pub max_history: usize,
     pub retrieval_k: usize,
+    pub min_similarity: f32,
+    pub mmr_lambda: f32,
+    pub retrieval_fetch_k: usize,
+    pub rerank_mode: RerankMode,
+    pub rerank_model: String,
+    pub rerank_concurrency: usize,
+    pub query_rewrite: bool,
+    pub query_expansion: QueryExpansion,
+    pub query_variants: usize,
+    pub context_expansion: ContextExpansion,
+    pub context_window: usize,
+    pub time_decay_half_life_days: f32,
+    pub time_decay_weight: f32,
+    pub episodic_memory: bool,
+    pub episodic_k: usize,
+    pub episodic_min_similarity: f32,
+    pub graph_extraction: bool,
+    pub graph_model: String,
+    pub include_globs: Vec<String>,
+    pub exclude_globs: Vec<String>,
+    pub max_file_size: u64,
+    pub watch_debounce_ms: u64,
+    pub ingest_concurrency: usize,
+    pub ingest_batch_size: usize,
+    pub quantization: Quantization,
+    pub keep_full_vectors: bool,
 }
 
 impl Settings {
@@ -28,6 +63,22 @@ impl Settings {
         let embedding_model = std::env::var("OLLAMA_EMBEDDING_MODEL")
             .unwrap_or_else(|_| "nomic-embed-text".to_string());
         
+        let embedding_backend = match std::env::var("EMBEDDING_BACKEND") {
+            Ok(value) => EmbeddingBackend::parse(&value)
+                .ok_or_else(|| anyhow::anyhow!("EMBEDDING_BACKEND must be ollama, local or auto, got '{}'", value))?,
+            Err(_) => EmbeddingBackend::Ollama,
+        };
+        
+        let local_embedding_dim = std::env::var("LOCAL_EMBEDDING_DIM")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(hashing::DEFAULT_DIMENSION);
+        
+        let embedding_cache = std::env::var("EMBEDDING_CACHE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(true);
+        
         let temperature = std::env::var("OLLAMA_TEMPERATURE")
             .unwrap_or_else(|_| "0.7".to_string())
             .parse()
@@ -37,10 +88,141 @@ impl Settings {
             std::env::var("DATA_DIR").unwrap_or_else(|_| "./data".to_string())
         );
         
+        let min_similarity = std::env::var("RETRIEVAL_MIN_SIMILARITY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.45);
+        
+        let mmr_lambda = std::env::var("RETRIEVAL_MMR_LAMBDA")
+            .ok()
+            .and_then(|v| v.parse::<f32>().ok())
+            .map(|v| v.clamp(0.0, 1.0))
+            .unwrap_or(0.7);
+        
+        let retrieval_fetch_k = std::env::var("RETRIEVAL_FETCH_K")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(20);
+        
+        let rerank_mode = match std::env::var("RERANK_MODE") {
+            Ok(value) => RerankMode::parse(&value)
+                .ok_or_else(|| anyhow::anyhow!("RERANK_MODE must be off, model or judge, got '{}'", value))?,
+            Err(_) => RerankMode::Off,
+        };
+        
+        // The judge defaults to the chat model; a dedicated reranker must be named
+        let rerank_model = std::env::var("RERANK_MODEL")
+            .unwrap_or_else(|_| ollama_model.clone());
+        
+        let rerank_concurrency = std::env::var("RERANK_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(4);
+        
+        let query_rewrite = std::env::var("QUERY_REWRITE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(true);
+        
+        let query_expansion = match std::env::var("QUERY_EXPANSION") {
+            Ok(value) => QueryExpansion::parse(&value)
+                .ok_or_else(|| anyhow::anyhow!("QUERY_EXPANSION must be off, multi or hyde, got '{}'", value))?,
+            Err(_) => QueryExpansion::Off,
+        };
+        
+        let query_variants = std::env::var("QUERY_VARIANTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3);
+        
+        let context_expansion = match std::env::var("CONTEXT_EXPANSION") {
+            Ok(value) => ContextExpansion::parse(&value)
+                .ok_or_else(|| anyhow::anyhow!("CONTEXT_EXPANSION must be off, neighbors or section, got '{}'", value))?,
+            Err(_) => ContextExpansion::Off,
+        };
+        
+        let context_window = std::env::var("CONTEXT_WINDOW")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1);
+        
+        // Unset or 0 leaves ranking to similarity alone
+        let time_decay_half_life_days = std::env::var("TIME_DECAY_HALF_LIFE_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0);
+        
+        let time_decay_weight = std::env::var("TIME_DECAY_WEIGHT")
+            .ok()
+            .and_then(|v| v.parse::<f32>().ok())
+            .map(|v| v.clamp(0.0, 1.0))
+            .unwrap_or(0.5);
+        
+        let episodic_memory = std::env::var("EPISODIC_MEMORY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(true);
+        
+        let episodic_k = std::env::var("EPISODIC_K")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3);
+        
+        let episodic_min_similarity = std::env::var("EPISODIC_MIN_SIMILARITY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.5);
+        
+        let graph_extraction = std::env::var("GRAPH_EXTRACTION")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(false);
+        
+        // Extraction defaults to the chat model, like the judge reranker
+        let graph_model = std::env::var("GRAPH_MODEL")
+            .unwrap_or_else(|_| ollama_model.clone());
+        
+        let include_globs = parse_list(std::env::var("INGEST_INCLUDE").ok());
+        let exclude_globs = parse_list(std::env::var("INGEST_EXCLUDE").ok());
+        
+        let max_file_size = std::env::var("INGEST_MAX_FILE_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10 * 1024 * 1024);
+        
+        let watch_debounce_ms = std::env::var("WATCH_DEBOUNCE_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1500);
+        
+        let ingest_concurrency = std::env::var("INGEST_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(4);
+        
+        let ingest_batch_size = std::env::var("INGEST_BATCH_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(64);
+        
+        let quantization = match std::env::var("VECTOR_QUANTIZATION") {
+            Ok(value) => Quantization::parse(&value)
+                .ok_or_else(|| anyhow::anyhow!("VECTOR_QUANTIZATION must be none, int8 or binary, got '{}'", value))?,
+            Err(_) => Quantization::None,
+        };
+        
+        let keep_full_vectors = std::env::var("VECTOR_KEEP_FULL")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(true);
+        
         Ok(Self {
             ollama_host,
             ollama_model,
             embedding_model,
+            embedding_backend,
+            local_embedding_dim,
+            embedding_cache,
             temperature,
             knowledge_dir: data_dir.join("knowledge_base"),
             conversations_dir: data_dir.join("conversations"),
@@ -49,6 +231,32 @@ impl Settings {
             chunk_overlap: 50,
             max_history: 6,
             retrieval_k: 3,
+            min_similarity,
+            mmr_lambda,
+            retrieval_fetch_k,
+            rerank_mode,
+            rerank_model,
+            rerank_concurrency,
+            query_rewrite,
+            query_expansion,
+            query_variants,
+            context_expansion,
+            context_window,
+            time_decay_half_life_days,
+            time_decay_weight,
+            episodic_memory,
+            episodic_k,
+            episodic_min_similarity,
+            graph_extraction,
+            graph_model,
+            include_globs,
+            exclude_globs,
+            max_file_size,
+            watch_debounce_ms,
+            ingest_concurrency,
+            ingest_batch_size,
+            quantization,
+            keep_full_vectors,
         })
     }
     
@@ -60,3 +268,9 @@ impl Settings {
     }
 }
 
+/// Splits a comma-separated environment value into trimmed, non-empty items.
+fn parse_list(value: Option<String>) -> Vec<String> {
+    value
+        .map(|v| v.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,103 @@
+use super::quantization::{vector_from_bytes, vector_to_bytes};
+use crate::{AssistantError, Result};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Lookups since the cache was opened.
+#[derive(Debug, Clone, Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Vectors stored on disk, across all models.
+    pub entries: usize,
+}
+
+impl CacheStats {
+    pub fn hit_rate(&self) -> f32 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f32 / lookups as f32
+        }
+    }
+}
+
+/// Persistent map from (embedding model, blake3 of the text) to vectors, so
+/// text that was embedded before - a re-learned file, a repeated query - is
+/// not sent to the model again. Clones share the same store and counters.
+///
+/// The cache is an optimisation only: failing to read or write it is
+/// treated as a miss.
+#[derive(Clone)]
+pub struct EmbeddingCache {
+    db: sled::Db,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+}
+
+impl EmbeddingCache {
+    pub fn open(path: &Path) -> Result<Self> {
+        let db = sled::open(path).map_err(cache_error)?;
+        Ok(Self {
+            db,
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    pub fn get(&self, model: &str, text: &str) -> Option<Vec<f32>> {
+        let cached = self.db.get(key(model, text)).ok().flatten();
+        match cached {
+            Some(bytes) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(vector_from_bytes(&bytes))
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, model: &str, text: &str, vector: &[f32]) {
+        let _ = self.db.insert(key(model, text), vector_to_bytes(vector));
+    }
+
+    /// Drops every vector cached for `model`, e.g. after the model was
+    /// re-pulled and may now embed differently.
+    pub fn clear_model(&self, model: &str) -> Result<usize> {
+        let mut removed = 0;
+        for entry in self.db.scan_prefix(model_prefix(model)).keys() {
+            self.db.remove(entry.map_err(cache_error)?).map_err(cache_error)?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.db.len(),
+        }
+    }
+}
+
+/// Keys group vectors by model so one model's entries can be dropped together.
+fn model_prefix(model: &str) -> Vec<u8> {
+    let mut prefix = model.as_bytes().to_vec();
+    prefix.push(0);
+    prefix
+}
+
+fn key(model: &str, text: &str) -> Vec<u8> {
+    let mut key = model_prefix(model);
+    key.extend_from_slice(blake3::hash(text.as_bytes()).as_bytes());
+    key
+}
+
+fn cache_error(e: sled::Error) -> AssistantError {
+    AssistantError::KnowledgeError(format!("embedding cache: {}", e))
+}
@@ -0,0 +1,285 @@
+use super::decay::{CollectionConfig, TimeDecay};
+use super::documents::Document;
+use super::embeddings::EmbeddingService;
+use super::manifest::EmbeddingStatus;
+use super::mmr::mmr_select;
+use super::quantization::{Quantization, VectorMemory};
+use super::vectorstore::{ScoredDocument, SearchOptions, VectorStore};
+use crate::{AssistantError, Result};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Collection that existed before collections did; it keeps using
+/// `knowledge_dir/documents.json`.
+pub const DEFAULT_COLLECTION: &str = "default";
+
+const COLLECTIONS_DIR: &str = "collections";
+const STORE_FILE: &str = "documents.json";
+const CONFIG_FILE: &str = "collection.json";
+
+/// Summary of one collection for listings.
+#[derive(Debug, Clone)]
+pub struct CollectionInfo {
+    pub name: String,
+    pub chunks: usize,
+    pub sources: usize,
+    /// Recency weighting applied when searching it, if any.
+    pub decay: Option<TimeDecay>,
+}
+
+/// Named, separately stored vector stores. Named collections live in
+/// `knowledge_dir/collections/<name>/`.
+pub struct Collections {
+    knowledge_dir: PathBuf,
+    stores: BTreeMap<String, VectorStore>,
+    embedding_service: EmbeddingService,
+    quantization: Quantization,
+    keep_full_vectors: bool,
+    configs: BTreeMap<String, CollectionConfig>,
+    /// Half-life used by collections that don't set their own; 0 is off.
+    default_half_life_days: f32,
+    decay_weight: f32,
+}
+
+impl Collections {
+    /// Opens the default collection and every named one found on disk.
+    pub fn open(
+        knowledge_dir: PathBuf,
+        embedding_service: EmbeddingService,
+        quantization: Quantization,
+        keep_full_vectors: bool,
+    ) -> Result<Self> {
+        let mut collections = Self {
+            knowledge_dir,
+            stores: BTreeMap::new(),
+            embedding_service,
+            quantization,
+            keep_full_vectors,
+            configs: BTreeMap::new(),
+            default_half_life_days: 0.0,
+            decay_weight: 0.0,
+        };
+
+        collections.open_store(DEFAULT_COLLECTION)?;
+        let dir = collections.knowledge_dir.join(COLLECTIONS_DIR);
+        if dir.is_dir() {
+            for entry in std::fs::read_dir(&dir)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().to_string();
+                // The default collection lives in `knowledge_dir` itself
+                if entry.path().is_dir() && name != DEFAULT_COLLECTION && validate_name(&name).is_ok() {
+                    collections.open_store(&name)?;
+                }
+            }
+        }
+        Ok(collections)
+    }
+
+    /// Weights search towards recent chunks in every collection that doesn't
+    /// set its own half-life. A `half_life_days` of 0 leaves decay off.
+    pub fn with_time_decay(mut self, half_life_days: f32, weight: f32) -> Self {
+        self.default_half_life_days = half_life_days;
+        self.decay_weight = weight;
+        let names = self.names();
+        for name in names {
+            let decay = self.decay_for(&name);
+            if let Some(store) = self.stores.get_mut(&name) {
+                store.set_time_decay(decay);
+            }
+        }
+        self
+    }
+
+    /// Sets the half-life of a collection, `Some(0.0)` turning decay off for
+    /// it and `None` returning it to the configured default.
+    pub fn set_half_life(&mut self, name: &str, half_life_days: Option<f32>) -> Result<Option<TimeDecay>> {
+        if !self.contains(name) {
+            return Err(unknown(name));
+        }
+        let path = self.dir_of(name).join(CONFIG_FILE);
+        let config = self.configs.entry(name.to_string()).or_default();
+        config.half_life_days = half_life_days;
+        config.save(&path)?;
+
+        let decay = self.decay_for(name);
+        self.get_mut(name)?.set_time_decay(decay);
+        Ok(decay)
+    }
+
+    /// The recency weighting searches of `name` use.
+    pub fn decay_for(&self, name: &str) -> Option<TimeDecay> {
+        let half_life_days = self
+            .configs
+            .get(name)
+            .and_then(|c| c.half_life_days)
+            .unwrap_or(self.default_half_life_days);
+        TimeDecay::new(half_life_days, self.decay_weight)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.stores.keys().cloned().collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.stores.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Result<&VectorStore> {
+        self.stores.get(name).ok_or_else(|| unknown(name))
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Result<&mut VectorStore> {
+        self.stores.get_mut(name).ok_or_else(|| unknown(name))
+    }
+
+    pub fn embedding_service(&self) -> &EmbeddingService {
+        &self.embedding_service
+    }
+
+    pub fn stores(&self) -> impl Iterator<Item = (&String, &VectorStore)> {
+        self.stores.iter()
+    }
+
+    pub fn stores_mut(&mut self) -> impl Iterator<Item = (&String, &mut VectorStore)> {
+        self.stores.iter_mut()
+    }
+
+    /// Every chunk cut from the parent `parent_id`, in chunk order.
+    pub fn family(&self, parent_id: &str) -> Vec<&Document> {
+        let mut chunks: Vec<&Document> = self
+            .stores
+            .values()
+            .flat_map(|store| store.documents())
+            .filter(|d| d.metadata.parent_id.as_deref() == Some(parent_id))
+            .collect();
+        chunks.sort_by_key(|d| d.metadata.chunk_index);
+        chunks
+    }
+    
+    /// Creates an empty collection, returning false if it already exists.
+    pub fn create(&mut self, name: &str) -> Result<bool> {
+        validate_name(name)?;
+        if self.contains(name) {
+            return Ok(false);
+        }
+        std::fs::create_dir_all(self.dir_of(name))?;
+        self.open_store(name)?;
+        Ok(true)
+    }
+
+    /// Deletes a named collection and its files, returning how many chunks it held.
+    pub fn drop_collection(&mut self, name: &str) -> Result<usize> {
+        if name == DEFAULT_COLLECTION {
+            return Err(AssistantError::KnowledgeError(
+                "the default collection can't be dropped; forget its sources instead".to_string(),
+            ));
+        }
+        let store = self.stores.remove(name).ok_or_else(|| unknown(name))?;
+        let chunks = store.count();
+        // Close the store (and its on-disk vector index) before deleting its files
+        drop(store);
+        std::fs::remove_dir_all(self.dir_of(name))?;
+        self.configs.remove(name);
+        Ok(chunks)
+    }
+
+    pub fn info(&self) -> Vec<CollectionInfo> {
+        self.stores
+            .iter()
+            .map(|(name, store)| CollectionInfo {
+                name: name.clone(),
+                chunks: store.count(),
+                sources: store.sources().len(),
+                decay: self.decay_for(name),
+            })
+            .collect()
+    }
+
+    /// Searches the named collections with one query embedding and merges
+    /// the hits by score. MMR, when enabled, runs over the merged pool.
+    pub async fn search(&self, names: &[String], query: &str, options: &SearchOptions) -> Result<Vec<ScoredDocument>> {
+        let stores = names.iter().map(|name| self.get(name)).collect::<Result<Vec<_>>>()?;
+        let query_embedding = self.embedding_service.embed(query).await?;
+        if let [store] = stores.as_slice() {
+            return Ok(store.search_by_embedding(&query_embedding, options));
+        }
+
+        let diversify = options.mmr_lambda < 1.0;
+        let pool = if diversify { options.fetch_k.max(options.k) } else { options.k };
+        let per_store = SearchOptions { k: pool, mmr_lambda: 1.0, ..options.clone() };
+        let mut hits: Vec<ScoredDocument> = stores
+            .iter()
+            .flat_map(|store| store.search_by_embedding(&query_embedding, &per_store))
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(pool);
+
+        if diversify {
+            Ok(mmr_select(hits, options.k, options.mmr_lambda))
+        } else {
+            Ok(hits)
+        }
+    }
+
+    /// Embedding status across all collections.
+    pub fn embedding_status(&self) -> EmbeddingStatus {
+        let statuses: Vec<EmbeddingStatus> = self.stores.values().map(|s| s.embedding_status()).collect();
+        EmbeddingStatus::combine(self.embedding_service.model(), &statuses)
+    }
+
+    pub fn vector_memory(&self) -> VectorMemory {
+        let mut total = VectorMemory {
+            quantization: self.quantization,
+            vectors: 0,
+            in_memory_bytes: 0,
+            full_precision_bytes: 0,
+            full_on_disk: self.keep_full_vectors && self.quantization != Quantization::None,
+        };
+        for store in self.stores.values() {
+            let memory = store.vector_memory();
+            total.vectors += memory.vectors;
+            total.in_memory_bytes += memory.in_memory_bytes;
+            total.full_precision_bytes += memory.full_precision_bytes;
+        }
+        total
+    }
+
+    fn open_store(&mut self, name: &str) -> Result<()> {
+        let path = self.dir_of(name).join(STORE_FILE);
+        let config = CollectionConfig::load(&self.dir_of(name).join(CONFIG_FILE))?;
+        self.configs.insert(name.to_string(), config);
+        let mut store = VectorStore::new(path, self.embedding_service.clone())
+            .with_quantization(self.quantization, self.keep_full_vectors)?;
+        store.set_time_decay(self.decay_for(name));
+        self.stores.insert(name.to_string(), store);
+        Ok(())
+    }
+
+    fn dir_of(&self, name: &str) -> PathBuf {
+        if name == DEFAULT_COLLECTION {
+            self.knowledge_dir.clone()
+        } else {
+            self.knowledge_dir.join(COLLECTIONS_DIR).join(name)
+        }
+    }
+}
+
+/// Collection names double as directory names: lowercase letters, digits,
+/// `-` and `_`, up to 64 characters.
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(AssistantError::KnowledgeError(format!(
+            "invalid collection name '{}': use lowercase letters, digits, '-' and '_'",
+            name
+        )))
+    }
+}
+
+fn unknown(name: &str) -> AssistantError {
+    AssistantError::KnowledgeError(format!("no collection named '{}'", name))
+}
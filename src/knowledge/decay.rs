@@ -0,0 +1,55 @@
+use crate::{AssistantError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Recency weighting for search: a chunk's similarity is scaled down as it
+/// ages, halving the decaying part every `half_life_days`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimeDecay {
+    pub half_life_days: f32,
+    /// Share of the score that decays, from 0 (similarity only) to 1
+    /// (similarity scaled entirely by recency).
+    pub weight: f32,
+}
+
+impl TimeDecay {
+    /// `None` when `half_life_days` is not positive, i.e. decay is off.
+    pub fn new(half_life_days: f32, weight: f32) -> Option<Self> {
+        (half_life_days > 0.0).then(|| Self { half_life_days, weight: weight.clamp(0.0, 1.0) })
+    }
+
+    /// `similarity` blended with how recent `timestamp` is at `now`.
+    pub fn apply(&self, similarity: f32, timestamp: DateTime<Utc>, now: DateTime<Utc>) -> f32 {
+        let age_days = (now - timestamp).num_seconds().max(0) as f32 / 86_400.0;
+        let recency = 0.5f32.powf(age_days / self.half_life_days);
+        similarity * ((1.0 - self.weight) + self.weight * recency)
+    }
+}
+
+/// Per-collection search settings, kept as `collection.json` in the
+/// collection's directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CollectionConfig {
+    /// Overrides the configured half-life; 0 turns decay off for this
+    /// collection. `None` follows the configured default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub half_life_days: Option<f32>,
+}
+
+impl CollectionConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json = std::fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(|e| AssistantError::SerializationError(e.to_string()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| AssistantError::SerializationError(e.to_string()))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
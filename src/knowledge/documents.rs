@@ -1,6 +1,8 @@
+use super::quantization::QuantizedVector;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Document {
@@ -9,24 +11,77 @@ pub struct Document {
     pub metadata: DocumentMetadata,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub embedding: Option<Vec<f32>>,
+    /// Model that produced `embedding`; vectors from different models are never compared.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+    /// Compressed form of the embedding, held instead of `embedding` when the
+    /// store is quantized.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quantized: Option<QuantizedVector>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocumentMetadata {
     pub source: String,
     pub timestamp: DateTime<Utc>,
+    /// blake3 fingerprint of `content`, used to skip duplicate chunks.
+    #[serde(default)]
+    pub content_hash: String,
+    /// blake3 fingerprint of the whole source file this chunk came from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_hash: Option<String>,
+    /// 1-based page number for paginated sources such as PDFs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    /// Heading of the section the chunk was taken from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// 1-based, inclusive line range in the source file, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_start: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_end: Option<usize>,
+    /// Id shared by all chunks cut from the same file or text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    /// 0-based position of the chunk within its parent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_index: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// ISO 639-3 code of the detected language, when detection was confident.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Free-form key/value annotations.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
 }
 
 impl Document {
     pub fn new(content: String, source: String) -> Self {
+        let content_hash = content_hash(&content);
+        let language = detect_language(&content);
         Self {
             id: Uuid::new_v4().to_string(),
             content,
             metadata: DocumentMetadata {
                 source,
                 timestamp: Utc::now(),
+                content_hash,
+                file_hash: None,
+                page: None,
+                section: None,
+                line_start: None,
+                line_end: None,
+                parent_id: None,
+                chunk_index: None,
+                tags: Vec::new(),
+                language,
+                fields: BTreeMap::new(),
             },
             embedding: None,
+            embedding_model: None,
+            quantized: None,
         }
     }
     
@@ -34,9 +89,71 @@ impl Document {
         self.embedding = Some(embedding);
         self
     }
+
+    pub fn with_file_hash(mut self, file_hash: String) -> Self {
+        self.metadata.file_hash = Some(file_hash);
+        self
+    }
+
+    pub fn with_location(mut self, page: Option<u32>, section: Option<String>) -> Self {
+        self.metadata.page = page;
+        self.metadata.section = section;
+        self
+    }
+
+    pub fn with_lines(mut self, start: usize, end: usize) -> Self {
+        self.metadata.line_start = Some(start);
+        self.metadata.line_end = Some(end);
+        self
+    }
+
+    pub fn with_position(mut self, parent_id: String, chunk_index: usize) -> Self {
+        self.metadata.parent_id = Some(parent_id);
+        self.metadata.chunk_index = Some(chunk_index);
+        self
+    }
+}
+
+impl DocumentMetadata {
+    /// Human-readable position within the source, e.g. `page 3` or `lines 10-24`.
+    pub fn location(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(page) = self.page {
+            parts.push(format!("page {}", page));
+        }
+        if let Some(section) = &self.section {
+            parts.push(format!("section \"{}\"", section));
+        }
+        match (self.line_start, self.line_end) {
+            (Some(start), Some(end)) if start != end => parts.push(format!("lines {}-{}", start, end)),
+            (Some(start), _) => parts.push(format!("line {}", start)),
+            _ => {}
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+/// ISO 639-3 code of `text`'s language, or `None` if detection isn't reliable.
+pub fn detect_language(text: &str) -> Option<String> {
+    whatlang::detect(text)
+        .filter(|info| info.is_reliable())
+        .map(|info| info.lang().code().to_string())
+}
+
+/// Hex-encoded blake3 hash of `data`.
+pub fn content_hash(data: impl AsRef<[u8]>) -> String {
+    blake3::hash(data.as_ref()).to_hex().to_string()
 }
 
 pub fn chunk_text(text: &str, chunk_size: usize, overlap: usize) -> Vec<String> {
+    chunk_text_with_offsets(text, chunk_size, overlap)
+        .into_iter()
+        .map(|(_, chunk)| chunk)
+        .collect()
+}
+
+/// Like [`chunk_text`], but also returns each chunk's starting character offset.
+pub fn chunk_text_with_offsets(text: &str, chunk_size: usize, overlap: usize) -> Vec<(usize, String)> {
     let chars: Vec<char> = text.chars().collect();
     let mut chunks = Vec::new();
     let mut start = 0;
@@ -46,7 +163,7 @@ pub fn chunk_text(text: &str, chunk_size: usize, overlap: usize) -> Vec<String>
         let chunk: String = chars[start..end].iter().collect();
         
         if !chunk.trim().is_empty() {
-            chunks.push(chunk);
+            chunks.push((start, chunk));
         }
         
         if end >= chars.len() {
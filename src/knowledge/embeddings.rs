@@ -1,18 +1,124 @@
+use super::cache::{CacheStats, EmbeddingCache};
+use super::hashing::{HashingEmbedder, DEFAULT_DIMENSION};
 use crate::llm::ollama::OllamaClient;
 use crate::AssistantError;
+use serde::{Deserialize, Serialize};
 
+/// Which embedder produces vectors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingBackend {
+    /// The configured Ollama embedding model.
+    Ollama,
+    /// The built-in [`HashingEmbedder`]; needs no model server.
+    Local,
+    /// Ollama, falling back to the local embedder when it fails.
+    Auto,
+}
+
+impl EmbeddingBackend {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "ollama" | "" => Some(Self::Ollama),
+            "local" | "hashing" | "offline" => Some(Self::Local),
+            "auto" | "fallback" => Some(Self::Auto),
+            _ => None,
+        }
+    }
+}
+
+/// A vector and the name of the model that produced it.
+#[derive(Debug, Clone)]
+pub struct Embedding {
+    pub vector: Vec<f32>,
+    pub model: String,
+}
+
+#[derive(Clone)]
 pub struct EmbeddingService {
     ollama: OllamaClient,
     model: String,
+    backend: EmbeddingBackend,
+    local: HashingEmbedder,
+    local_model: String,
+    cache: Option<EmbeddingCache>,
 }
 
 impl EmbeddingService {
     pub fn new(ollama: OllamaClient, model: String) -> Self {
-        Self { ollama, model }
+        let local = HashingEmbedder::new(DEFAULT_DIMENSION);
+        Self {
+            ollama,
+            model,
+            backend: EmbeddingBackend::Ollama,
+            local_model: local.model_name(),
+            local,
+            cache: None,
+        }
     }
     
+    pub fn with_backend(mut self, backend: EmbeddingBackend, local: HashingEmbedder) -> Self {
+        self.backend = backend;
+        self.local_model = local.model_name();
+        self.local = local;
+        self
+    }
+    
+    /// Reuses vectors from `cache` for text the model has embedded before.
+    pub fn with_cache(mut self, cache: EmbeddingCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+    
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(|cache| cache.stats())
+    }
+    
+    /// Drops cached vectors of the primary model so they are computed afresh.
+    pub fn clear_cache(&self) -> Result<usize, AssistantError> {
+        match &self.cache {
+            Some(cache) => cache.clear_model(self.model()),
+            None => Ok(0),
+        }
+    }
+    
+    /// The primary model: the one stores are built with and migrated to.
+    pub fn model(&self) -> &str {
+        match self.backend {
+            EmbeddingBackend::Local => &self.local_model,
+            _ => &self.model,
+        }
+    }
+    
+    /// Embeds `text` with the primary model only. Ollama vectors are looked
+    /// up in and added to the cache; local ones are cheaper to recompute.
     pub async fn embed_text(&self, text: &str) -> Result<Vec<f32>, AssistantError> {
-        self.ollama.embed(&self.model, text).await
+        if self.backend == EmbeddingBackend::Local {
+            return Ok(self.local.embed(text));
+        }
+        
+        if let Some(vector) = self.cache.as_ref().and_then(|cache| cache.get(&self.model, text)) {
+            return Ok(vector);
+        }
+        let vector = self.ollama.embed(&self.model, text).await?;
+        if let Some(cache) = &self.cache {
+            cache.insert(&self.model, text, &vector);
+        }
+        Ok(vector)
+    }
+    
+    /// Embeds `text`, falling back to the local embedder when the backend is
+    /// [`EmbeddingBackend::Auto`] and the primary model fails. The result
+    /// names the model that actually produced it.
+    pub async fn embed(&self, text: &str) -> Result<Embedding, AssistantError> {
+        match self.embed_text(text).await {
+            Ok(vector) => Ok(Embedding { vector, model: self.model().to_string() }),
+            Err(_) if self.backend == EmbeddingBackend::Auto => Ok(Embedding {
+                vector: self.local.embed(text),
+                model: self.local_model.clone(),
+            }),
+            Err(e) => Err(e),
+        }
     }
     
     pub async fn embed_batch(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, AssistantError> {
@@ -27,15 +133,45 @@ impl EmbeddingService {
     }
 }
 
+/// Cosine similarity of two vectors; vectors of different lengths come from
+/// different embedding spaces and score 0.
 pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-    let mag_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-    let mag_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    
+    let mag_a = dot(a, a).sqrt();
+    let mag_b = dot(b, b).sqrt();
     
     if mag_a == 0.0 || mag_b == 0.0 {
         0.0
     } else {
-        dot / (mag_a * mag_b)
+        dot(a, b) / (mag_a * mag_b)
     }
 }
 
+/// Dot product of two equally long vectors. Summing into independent lanes
+/// lets the compiler vectorize the loop, which a single running sum (a
+/// strict left-to-right float addition) would prevent.
+pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    const LANES: usize = 8;
+    let mut sums = [0.0f32; LANES];
+    let (a_chunks, b_chunks) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+    let tail: f32 = a_chunks.remainder().iter().zip(b_chunks.remainder()).map(|(x, y)| x * y).sum();
+    for (x, y) in a_chunks.zip(b_chunks) {
+        for lane in 0..LANES {
+            sums[lane] += x[lane] * y[lane];
+        }
+    }
+    sums.iter().sum::<f32>() + tail
+}
+
+/// Scales `vector` to unit length in place; a zero vector is left as is.
+pub fn normalize(vector: &mut [f32]) {
+    let norm = dot(vector, vector).sqrt();
+    if norm > 0.0 {
+        for x in vector.iter_mut() {
+            *x /= norm;
+        }
+    }
+}
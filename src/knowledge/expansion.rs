@@ -0,0 +1,183 @@
+use super::documents::Document;
+use super::vectorstore::ScoredDocument;
+use serde::{Deserialize, Serialize};
+
+/// Upper bound on chunks pulled in around a hit by section expansion.
+const SECTION_MAX_CHUNKS: usize = 8;
+/// Shorter shared runs between chunks are treated as coincidence, not overlap.
+const MIN_OVERLAP: usize = 8;
+
+/// How a retrieved chunk is widened before it is shown to the model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextExpansion {
+    Off,
+    /// The chunks just before and after the hit.
+    Neighbors,
+    /// The run of chunks sharing the hit's page and section heading.
+    Section,
+}
+
+impl ContextExpansion {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "off" | "none" | "" => Some(Self::Off),
+            "neighbors" | "neighbours" | "window" => Some(Self::Neighbors),
+            "section" | "parent" => Some(Self::Section),
+            _ => None,
+        }
+    }
+}
+
+/// A contiguous range of chunks from one parent, represented by its best hit.
+struct Span {
+    hit: ScoredDocument,
+    parent_id: String,
+    first: usize,
+    last: usize,
+}
+
+/// Widens each hit with surrounding chunks from the same parent and merges
+/// hits whose expansions overlap or touch into one passage, so the same
+/// text is never shown twice. `family` returns every chunk sharing a parent
+/// id. Hits keep their order; merged passages take the place of their
+/// best-ranked hit.
+pub fn expand_hits<'a>(
+    hits: Vec<ScoredDocument>,
+    mode: ContextExpansion,
+    window: usize,
+    family: impl Fn(&str) -> Vec<&'a Document>,
+) -> Vec<ScoredDocument> {
+    if mode == ContextExpansion::Off {
+        return hits;
+    }
+
+    let mut spans: Vec<Result<Span, ScoredDocument>> = Vec::with_capacity(hits.len());
+    for hit in hits {
+        let meta = &hit.document.metadata;
+        let (Some(parent_id), Some(index)) = (meta.parent_id.clone(), meta.chunk_index) else {
+            spans.push(Err(hit));
+            continue;
+        };
+        let siblings = family(&parent_id);
+        let (first, last) = match mode {
+            ContextExpansion::Section if meta.section.is_some() || meta.page.is_some() => {
+                section_bounds(&siblings, &hit.document, index)
+            }
+            _ => (index.saturating_sub(window), index + window),
+        };
+
+        let overlapping = spans.iter_mut().flatten().find(|span| {
+            span.parent_id == parent_id && first <= span.last + 1 && span.first <= last + 1
+        });
+        match overlapping {
+            Some(span) => {
+                span.first = span.first.min(first);
+                span.last = span.last.max(last);
+                span.hit.score = span.hit.score.max(hit.score);
+            }
+            None => spans.push(Ok(Span { hit, parent_id, first, last })),
+        }
+    }
+    coalesce(&mut spans);
+
+    spans
+        .into_iter()
+        .map(|span| match span {
+            Ok(span) => {
+                let chunks: Vec<&Document> = family(&span.parent_id)
+                    .into_iter()
+                    .filter(|d| d.metadata.chunk_index.is_some_and(|i| i >= span.first && i <= span.last))
+                    .collect();
+                merge(span.hit, &chunks)
+            }
+            Err(hit) => hit,
+        })
+        .collect()
+}
+
+/// Chunk range around `index` that shares `hit`'s page and section, capped
+/// at [`SECTION_MAX_CHUNKS`] and centred on the hit.
+fn section_bounds(siblings: &[&Document], hit: &Document, index: usize) -> (usize, usize) {
+    let same_section = |d: &Document| {
+        d.metadata.section == hit.metadata.section && d.metadata.page == hit.metadata.page
+    };
+    let indices: Vec<usize> = siblings
+        .iter()
+        .filter(|d| same_section(d))
+        .filter_map(|d| d.metadata.chunk_index)
+        .collect();
+
+    let mut first = index;
+    while first > 0 && indices.contains(&(first - 1)) && index - first + 1 < SECTION_MAX_CHUNKS / 2 {
+        first -= 1;
+    }
+    let mut last = index;
+    while indices.contains(&(last + 1)) && last - first + 1 < SECTION_MAX_CHUNKS {
+        last += 1;
+    }
+    (first, last)
+}
+
+/// Merges spans that grew into each other after they were created.
+fn coalesce(spans: &mut Vec<Result<Span, ScoredDocument>>) {
+    let mut i = 0;
+    while i < spans.len() {
+        let mut j = i + 1;
+        while j < spans.len() {
+            let touching = match (&spans[i], &spans[j]) {
+                (Ok(a), Ok(b)) => {
+                    a.parent_id == b.parent_id && b.first <= a.last + 1 && a.first <= b.last + 1
+                }
+                _ => false,
+            };
+            if touching {
+                if let (Ok(b), Ok(a)) = (spans.remove(j), &mut spans[i]) {
+                    a.first = a.first.min(b.first);
+                    a.last = a.last.max(b.last);
+                    a.hit.score = a.hit.score.max(b.hit.score);
+                }
+                j = i + 1;
+            } else {
+                j += 1;
+            }
+        }
+        i += 1;
+    }
+}
+
+/// Joins `chunks` (in chunk order) into `hit`'s document, dropping the text
+/// consecutive chunks share through chunk overlap.
+fn merge(mut hit: ScoredDocument, chunks: &[&Document]) -> ScoredDocument {
+    let mut chunks = chunks.to_vec();
+    chunks.sort_by_key(|d| d.metadata.chunk_index);
+    if chunks.len() <= 1 {
+        return hit;
+    }
+
+    let mut content = String::new();
+    for chunk in &chunks {
+        let shared = shared_overlap(&content, &chunk.content);
+        if shared == 0 && !content.is_empty() {
+            content.push_str("\n\n");
+        }
+        content.extend(chunk.content.chars().skip(shared));
+    }
+
+    let meta = &mut hit.document.metadata;
+    meta.line_start = chunks.iter().filter_map(|d| d.metadata.line_start).min().or(meta.line_start);
+    meta.line_end = chunks.iter().filter_map(|d| d.metadata.line_end).max().or(meta.line_end);
+    hit.document.content = content;
+    hit
+}
+
+/// Length in chars of the longest suffix of `previous` that starts `next`.
+fn shared_overlap(previous: &str, next: &str) -> usize {
+    let next: Vec<char> = next.chars().collect();
+    let mut tail: Vec<char> = previous.chars().rev().take(next.len()).collect();
+    tail.reverse();
+    (MIN_OVERLAP..=tail.len())
+        .rev()
+        .find(|&n| tail[tail.len() - n..] == next[..n])
+        .unwrap_or(0)
+}
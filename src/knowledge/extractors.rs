@@ -0,0 +1,373 @@
+use crate::utils::file::is_text_file;
+use crate::{AssistantError, Result};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use scraper::{ElementRef, Html, Node};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+const PDF: &str = "application/pdf";
+const HTML: &str = "text/html";
+const XHTML: &str = "application/xhtml+xml";
+const DOCX: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+/// A run of extracted text and where it came from in the original file.
+#[derive(Debug, Clone, Default)]
+pub struct ExtractedSection {
+    pub text: String,
+    pub page: Option<u32>,
+    pub section: Option<String>,
+    /// Line in the original file where `text` begins, for extractors that
+    /// keep the file's lines intact.
+    pub first_line: Option<usize>,
+}
+
+/// Turns the raw bytes of one file format into plain text sections.
+pub trait Extractor: Send + Sync {
+    /// MIME types this extractor is registered under.
+    fn mime_types(&self) -> &'static [&'static str];
+
+    fn extract(&self, bytes: &[u8]) -> Result<Vec<ExtractedSection>>;
+}
+
+/// Extractors keyed on MIME type, with plain text as the fallback for
+/// anything that looks like text.
+pub struct ExtractorRegistry {
+    extractors: Vec<Box<dyn Extractor>>,
+    by_mime: HashMap<&'static str, usize>,
+    plain_text: PlainTextExtractor,
+}
+
+impl Default for ExtractorRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            extractors: Vec::new(),
+            by_mime: HashMap::new(),
+            plain_text: PlainTextExtractor,
+        };
+        registry.register(Box::new(PdfExtractor));
+        registry.register(Box::new(HtmlExtractor));
+        registry.register(Box::new(DocxExtractor));
+        registry
+    }
+}
+
+impl ExtractorRegistry {
+    /// Adds `extractor`, replacing any earlier one for the same MIME types.
+    pub fn register(&mut self, extractor: Box<dyn Extractor>) {
+        let index = self.extractors.len();
+        for mime in extractor.mime_types() {
+            self.by_mime.insert(mime, index);
+        }
+        self.extractors.push(extractor);
+    }
+
+    pub fn for_path(&self, path: &Path) -> Option<&dyn Extractor> {
+        let registered = mime_guess::from_path(path)
+            .iter()
+            .find_map(|mime| self.by_mime.get(mime.essence_str()).copied());
+
+        match registered {
+            Some(index) => Some(self.extractors[index].as_ref()),
+            None if is_text_file(path) => Some(&self.plain_text),
+            None => None,
+        }
+    }
+
+    pub fn supports(&self, path: &Path) -> bool {
+        self.for_path(path).is_some()
+    }
+
+    /// Extracts `bytes` read from `path`, picking the extractor by the path's MIME type.
+    pub fn extract(&self, path: &Path, bytes: &[u8]) -> Result<Vec<ExtractedSection>> {
+        let extractor = self.for_path(path).ok_or_else(|| {
+            AssistantError::KnowledgeError(format!("unsupported file type: {}", path.display()))
+        })?;
+        extractor.extract(bytes)
+    }
+}
+
+pub struct PlainTextExtractor;
+
+impl Extractor for PlainTextExtractor {
+    fn mime_types(&self) -> &'static [&'static str] {
+        &["text/plain"]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<Vec<ExtractedSection>> {
+        let text = String::from_utf8(bytes.to_vec())
+            .map_err(|e| AssistantError::KnowledgeError(format!("not valid UTF-8: {}", e)))?;
+        Ok(vec![ExtractedSection { text, first_line: Some(1), ..Default::default() }])
+    }
+}
+
+/// One section per PDF page.
+pub struct PdfExtractor;
+
+impl Extractor for PdfExtractor {
+    fn mime_types(&self) -> &'static [&'static str] {
+        &[PDF]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<Vec<ExtractedSection>> {
+        // pdf-extract panics on some malformed documents
+        let pages = std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem_by_pages(bytes))
+            .map_err(|_| AssistantError::KnowledgeError("PDF parser crashed".to_string()))?
+            .map_err(|e| AssistantError::KnowledgeError(format!("PDF error: {}", e)))?;
+
+        Ok(pages
+            .into_iter()
+            .enumerate()
+            .map(|(i, text)| ExtractedSection {
+                text: tidy(&text),
+                page: Some(i as u32 + 1),
+                ..Default::default()
+            })
+            .filter(|s| !s.text.is_empty())
+            .collect())
+    }
+}
+
+/// One section per `h1`-`h3` heading; scripts, styles and the head are dropped.
+pub struct HtmlExtractor;
+
+const HTML_HEADINGS: &[&str] = &["h1", "h2", "h3"];
+const HTML_SKIPPED: &[&str] = &["head", "script", "style", "noscript", "template", "svg"];
+const HTML_BLOCKS: &[&str] = &[
+    "p", "div", "br", "li", "tr", "pre", "blockquote", "section", "article", "h4", "h5", "h6",
+    "table", "ul", "ol", "dt", "dd",
+];
+
+impl Extractor for HtmlExtractor {
+    fn mime_types(&self) -> &'static [&'static str] {
+        &[HTML, XHTML]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<Vec<ExtractedSection>> {
+        let html = String::from_utf8_lossy(bytes);
+        let document = Html::parse_document(&html);
+        let mut sections = vec![ExtractedSection::default()];
+
+        for node in document.root_element().descendants() {
+            match node.value() {
+                Node::Element(element) if HTML_HEADINGS.contains(&element.name()) => {
+                    let in_skipped = node.ancestors().any(|a| {
+                        a.value().as_element().is_some_and(|e| HTML_SKIPPED.contains(&e.name()))
+                    });
+                    if in_skipped {
+                        continue;
+                    }
+                    let title = ElementRef::wrap(node)
+                        .map(|e| collapse_whitespace(&e.text().collect::<Vec<_>>().join(" ")))
+                        .unwrap_or_default();
+                    sections.push(ExtractedSection {
+                        text: format!("{}\n", title),
+                        section: Some(title).filter(|t| !t.is_empty()),
+                        ..Default::default()
+                    });
+                }
+                Node::Element(element) if HTML_BLOCKS.contains(&element.name()) => {
+                    if let Some(current) = sections.last_mut() {
+                        current.text.push('\n');
+                    }
+                }
+                Node::Text(text) => {
+                    let hidden = node.ancestors().any(|a| {
+                        a.value().as_element().is_some_and(|e| {
+                            HTML_SKIPPED.contains(&e.name()) || HTML_HEADINGS.contains(&e.name())
+                        })
+                    });
+                    let text = collapse_whitespace(text);
+                    if hidden || text.is_empty() {
+                        continue;
+                    }
+                    if let Some(current) = sections.last_mut() {
+                        current.text.push_str(&text);
+                        current.text.push(' ');
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(finish(sections))
+    }
+}
+
+/// One section per paragraph styled as a heading.
+pub struct DocxExtractor;
+
+impl Extractor for DocxExtractor {
+    fn mime_types(&self) -> &'static [&'static str] {
+        &[DOCX]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<Vec<ExtractedSection>> {
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))
+            .map_err(|e| AssistantError::KnowledgeError(format!("DOCX error: {}", e)))?;
+        let mut xml = String::new();
+        archive
+            .by_name("word/document.xml")
+            .map_err(|e| AssistantError::KnowledgeError(format!("DOCX error: {}", e)))?
+            .read_to_string(&mut xml)?;
+
+        let mut reader = Reader::from_str(&xml);
+        let mut sections = vec![ExtractedSection::default()];
+        let mut paragraph = String::new();
+        let mut is_heading = false;
+        let mut in_text = false;
+
+        loop {
+            let event = reader
+                .read_event()
+                .map_err(|e| AssistantError::KnowledgeError(format!("DOCX error: {}", e)))?;
+            match event {
+                Event::Start(e) if e.name().as_ref() == b"w:t" => in_text = true,
+                Event::End(e) if e.name().as_ref() == b"w:t" => in_text = false,
+                Event::Text(e) if in_text => {
+                    let text = e
+                        .unescape()
+                        .map_err(|e| AssistantError::KnowledgeError(format!("DOCX error: {}", e)))?;
+                    paragraph.push_str(&text);
+                }
+                Event::Empty(e) => match e.name().as_ref() {
+                    b"w:tab" => paragraph.push('\t'),
+                    b"w:br" => paragraph.push('\n'),
+                    b"w:pStyle" => {
+                        is_heading = e.attributes().flatten().any(|a| {
+                            a.key.as_ref() == b"w:val"
+                                && String::from_utf8_lossy(&a.value).to_lowercase().starts_with("heading")
+                        });
+                    }
+                    _ => {}
+                },
+                Event::End(e) if e.name().as_ref() == b"w:p" => {
+                    let text = std::mem::take(&mut paragraph);
+                    if is_heading && !text.trim().is_empty() {
+                        sections.push(ExtractedSection {
+                            text: format!("{}\n", text.trim()),
+                            section: Some(text.trim().to_string()),
+                            ..Default::default()
+                        });
+                    } else if let Some(current) = sections.last_mut() {
+                        current.text.push_str(&text);
+                        current.text.push('\n');
+                    }
+                    is_heading = false;
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        Ok(finish(sections))
+    }
+}
+
+fn finish(sections: Vec<ExtractedSection>) -> Vec<ExtractedSection> {
+    sections
+        .into_iter()
+        .map(|s| ExtractedSection { text: tidy(&s.text), ..s })
+        .filter(|s| !s.text.is_empty())
+        .collect()
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Trims each line and squeezes runs of blank lines down to one.
+fn tidy(text: &str) -> String {
+    let mut out = String::new();
+    let mut blank = false;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            blank = !out.is_empty();
+            continue;
+        }
+        if blank {
+            out.push('\n');
+            blank = false;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(line);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn titles(sections: &[ExtractedSection]) -> Vec<Option<&str>> {
+        sections.iter().map(|s| s.section.as_deref()).collect()
+    }
+
+    fn docx(body: &str) -> Vec<u8> {
+        let mut archive = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        archive.start_file("word/document.xml", zip::write::SimpleFileOptions::default()).unwrap();
+        write!(
+            archive,
+            r#"<?xml version="1.0"?><w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>{}</w:body></w:document>"#,
+            body
+        )
+        .unwrap();
+        archive.finish().unwrap().into_inner()
+    }
+
+    fn paragraph(style: Option<&str>, text: &str) -> String {
+        let style = style.map(|s| format!(r#"<w:pPr><w:pStyle w:val="{}"/></w:pPr>"#, s)).unwrap_or_default();
+        format!("<w:p>{}<w:r><w:t>{}</w:t></w:r></w:p>", style, text)
+    }
+
+    #[test]
+    fn html_headings_start_sections() {
+        let html = "<html><body><p>Preamble text.</p>\
+            <h1>Install</h1><p>Run the   installer.</p>\
+            <h2>Configure</h2><ul><li>Set the port</li><li>Set the host</li></ul></body></html>";
+        let sections = HtmlExtractor.extract(html.as_bytes()).unwrap();
+        assert_eq!(titles(&sections), vec![None, Some("Install"), Some("Configure")]);
+        assert_eq!(sections[0].text, "Preamble text.");
+        assert_eq!(sections[1].text, "Install\n\nRun the installer.");
+        assert_eq!(sections[2].text, "Configure\n\nSet the port\nSet the host");
+    }
+
+    #[test]
+    fn html_scripts_and_styles_are_dropped() {
+        let html = "<html><head><title>Page</title><style>body { color: red }</style></head>\
+            <body><script>var secret = 1;</script><p>Visible</p><noscript>Enable JS</noscript></body></html>";
+        let sections = HtmlExtractor.extract(html.as_bytes()).unwrap();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].text, "Visible");
+    }
+
+    #[test]
+    fn docx_heading_paragraphs_start_sections() {
+        let body = [
+            paragraph(None, "Summary &amp; scope"),
+            paragraph(Some("Heading1"), "Budget"),
+            paragraph(None, "Costs went up."),
+            paragraph(Some("Normal"), "Revenue too."),
+        ]
+        .concat();
+        let sections = DocxExtractor.extract(&docx(&body)).unwrap();
+        assert_eq!(titles(&sections), vec![None, Some("Budget")]);
+        assert_eq!(sections[0].text, "Summary & scope");
+        assert_eq!(sections[1].text, "Budget\nCosts went up.\nRevenue too.");
+    }
+
+    #[test]
+    fn docx_without_a_document_part_is_an_error() {
+        assert!(DocxExtractor.extract(b"not a zip").is_err());
+    }
+
+    #[test]
+    fn tidy_trims_lines_and_squeezes_blank_runs() {
+        assert_eq!(tidy("\n\n  first  \n\n\n\t\nsecond\nthird   \n\n"), "first\n\nsecond\nthird");
+        assert_eq!(tidy(" \n \n"), "");
+    }
+}
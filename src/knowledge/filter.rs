@@ -0,0 +1,117 @@
+use super::documents::DocumentMetadata;
+use crate::{AssistantError, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::fmt;
+
+/// Restricts which documents a search may return. All set conditions must
+/// hold; an empty filter matches everything.
+///
+/// Parsed from space-separated terms such as
+/// `source:notes/work tag:work after:2024-01-01 before:2024-06-30 lang:eng project=billing`.
+/// Repeating `tag:` requires every listed tag.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchFilter {
+    pub source_prefix: Option<String>,
+    pub tags: Vec<String>,
+    pub after: Option<DateTime<Utc>>,
+    pub before: Option<DateTime<Utc>>,
+    pub language: Option<String>,
+    pub fields: Vec<(String, String)>,
+}
+
+impl SearchFilter {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let mut filter = Self::default();
+        
+        for term in expr.split_whitespace() {
+            if let Some((key, value)) = term.split_once(':') {
+                match key.to_lowercase().as_str() {
+                    "source" => filter.source_prefix = Some(value.to_string()),
+                    "tag" => filter.tags.push(value.to_string()),
+                    "after" => filter.after = Some(parse_date(value)?),
+                    "before" => filter.before = Some(parse_date(value)?),
+                    "lang" | "language" => filter.language = Some(value.to_string()),
+                    _ => return Err(invalid(term)),
+                }
+            } else if let Some((key, value)) = term.split_once('=') {
+                filter.fields.push((key.to_string(), value.to_string()));
+            } else {
+                return Err(invalid(term));
+            }
+        }
+        
+        Ok(filter)
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+    
+    pub fn matches(&self, metadata: &DocumentMetadata) -> bool {
+        if let Some(prefix) = &self.source_prefix {
+            if !metadata.source.starts_with(prefix.as_str()) {
+                return false;
+            }
+        }
+        if !self.tags.iter().all(|t| metadata.tags.iter().any(|m| m.eq_ignore_ascii_case(t))) {
+            return false;
+        }
+        if self.after.is_some_and(|after| metadata.timestamp < after) {
+            return false;
+        }
+        if self.before.is_some_and(|before| metadata.timestamp >= before) {
+            return false;
+        }
+        if let Some(language) = &self.language {
+            if !metadata.language.as_deref().is_some_and(|code| language_matches(code, language)) {
+                return false;
+            }
+        }
+        self.fields
+            .iter()
+            .all(|(key, value)| metadata.fields.get(key) == Some(value))
+    }
+}
+
+impl fmt::Display for SearchFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut terms = Vec::new();
+        if let Some(prefix) = &self.source_prefix {
+            terms.push(format!("source:{}", prefix));
+        }
+        terms.extend(self.tags.iter().map(|t| format!("tag:{}", t)));
+        if let Some(after) = self.after {
+            terms.push(format!("after:{}", after.format("%Y-%m-%d")));
+        }
+        if let Some(before) = self.before {
+            terms.push(format!("before:{}", before.format("%Y-%m-%d")));
+        }
+        if let Some(language) = &self.language {
+            terms.push(format!("lang:{}", language));
+        }
+        terms.extend(self.fields.iter().map(|(k, v)| format!("{}={}", k, v)));
+        write!(f, "{}", terms.join(" "))
+    }
+}
+
+/// Accepts either the stored ISO 639-3 code or the language's English name.
+fn language_matches(code: &str, wanted: &str) -> bool {
+    code.eq_ignore_ascii_case(wanted)
+        || whatlang::Lang::from_code(code).is_some_and(|lang| lang.eng_name().eq_ignore_ascii_case(wanted))
+}
+
+fn parse_date(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Ok(datetime.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+        .map_err(|_| AssistantError::KnowledgeError(format!("invalid date '{}', expected YYYY-MM-DD", value)))
+}
+
+fn invalid(term: &str) -> AssistantError {
+    AssistantError::KnowledgeError(format!(
+        "invalid filter term '{}' (use source:, tag:, after:, before:, lang: or key=value)",
+        term
+    ))
+}
@@ -0,0 +1,36 @@
+use super::vectorstore::ScoredDocument;
+use std::collections::HashMap;
+
+/// Damping constant from the original RRF paper; keeps a single top rank
+/// from dominating the fused order.
+const RRF_K: f32 = 60.0;
+
+/// Reciprocal Rank Fusion of several ranked result lists.
+///
+/// Each document scores `sum(1 / (RRF_K + rank))` over the lists it appears
+/// in and the result is ordered by that. The returned hits keep their best
+/// similarity score, so downstream cutoffs and MMR still see similarities.
+pub fn reciprocal_rank_fusion(lists: Vec<Vec<ScoredDocument>>) -> Vec<ScoredDocument> {
+    let mut fused: HashMap<String, (f32, ScoredDocument)> = HashMap::new();
+
+    for list in lists {
+        for (rank, hit) in list.into_iter().enumerate() {
+            let contribution = 1.0 / (RRF_K + rank as f32 + 1.0);
+            match fused.get_mut(&hit.document.id) {
+                Some((rrf, best)) => {
+                    *rrf += contribution;
+                    if hit.score > best.score {
+                        *best = hit;
+                    }
+                }
+                None => {
+                    fused.insert(hit.document.id.clone(), (contribution, hit));
+                }
+            }
+        }
+    }
+
+    let mut ranked: Vec<(f32, ScoredDocument)> = fused.into_values().collect();
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.score.total_cmp(&a.1.score)));
+    ranked.into_iter().map(|(_, hit)| hit).collect()
+}
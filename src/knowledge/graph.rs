@@ -0,0 +1,477 @@
+use crate::llm::ollama::OllamaClient;
+use crate::{AssistantError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Longest entity name or predicate kept from an extraction, in characters.
+const MAX_NAME_CHARS: usize = 100;
+
+/// Longest entity name, in words, looked for in chat messages.
+const MAX_MENTION_WORDS: usize = 8;
+
+/// Entities and relations the model found in one chunk.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Extraction {
+    #[serde(default)]
+    pub entities: Vec<ExtractedEntity>,
+    #[serde(default)]
+    pub relations: Vec<ExtractedRelation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractedEntity {
+    pub name: String,
+    #[serde(default, rename = "type")]
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractedRelation {
+    pub subject: String,
+    pub predicate: String,
+    pub object: String,
+}
+
+/// Asks a chat model for the entities and relations stated in a chunk.
+#[derive(Clone)]
+pub struct GraphExtractor {
+    ollama: OllamaClient,
+    model: String,
+}
+
+impl GraphExtractor {
+    pub fn new(ollama: OllamaClient, model: String) -> Self {
+        Self { ollama, model }
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub async fn extract(&self, text: &str) -> Result<Extraction> {
+        let prompt = format!(
+            "Extract the named entities (people, teams, services, projects, organisations, \
+            products, technologies, places) in the text and the relations between them that \
+            the text states. Use short predicates such as \"owns\", \"depends on\" or \
+            \"works on\". Reply with JSON only, in the form \
+            {{\"entities\": [{{\"name\": \"...\", \"type\": \"...\"}}], \
+            \"relations\": [{{\"subject\": \"...\", \"predicate\": \"...\", \"object\": \"...\"}}]}}. \
+            Use empty lists when there are none.\n\nText:\n{}\n\nJSON:",
+            text
+        );
+        let reply = self.ollama.generate(&self.model, &prompt, false, 0.0).await?;
+        parse_extraction(&reply)
+    }
+}
+
+/// An entity and every source it was mentioned in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entity {
+    pub name: String,
+    pub kind: String,
+    pub sources: Vec<String>,
+}
+
+/// A `subject predicate object` fact and the sources stating it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Relation {
+    pub subject: String,
+    pub predicate: String,
+    pub object: String,
+    pub sources: Vec<String>,
+}
+
+impl Relation {
+    pub fn describe(&self) -> String {
+        format!("{} {} {}", self.subject, self.predicate, self.object)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GraphStats {
+    pub entities: usize,
+    pub relations: usize,
+}
+
+/// An entity together with the relations it takes part in.
+#[derive(Debug, Clone)]
+pub struct Neighbourhood {
+    pub entity: Entity,
+    /// Relations with the entity as subject.
+    pub outgoing: Vec<Relation>,
+    /// Relations with the entity as object.
+    pub incoming: Vec<Relation>,
+}
+
+impl Neighbourhood {
+    /// The part of the neighbourhood stated by sources `keep` accepts, or
+    /// `None` if none of them mention the entity.
+    pub fn within(mut self, keep: impl Fn(&str) -> bool) -> Option<Self> {
+        let stated = |sources: &[String]| sources.iter().any(|source| keep(source));
+        if !stated(&self.entity.sources) {
+            return None;
+        }
+        self.outgoing.retain(|relation| stated(&relation.sources));
+        self.incoming.retain(|relation| stated(&relation.sources));
+        Some(self)
+    }
+}
+
+/// Entities and relations extracted from learned knowledge, kept in sled
+/// next to the knowledge base. Entities are keyed by their lowercased name,
+/// so "Billing Service" and "billing service" are the same node.
+pub struct KnowledgeGraph {
+    entities: sled::Tree,
+    /// Keyed by subject, predicate and object, so a prefix scan on the
+    /// subject finds its outgoing relations.
+    relations: sled::Tree,
+    /// Object, then the relation key, for finding incoming relations.
+    incoming: sled::Tree,
+    /// Extraction per chunk content, so re-learning a changed file only
+    /// sends its new chunks to the model.
+    extractions: sled::Tree,
+    /// Source, then the key of each entity and relation it mentions, so a
+    /// source can be forgotten without scanning the whole graph.
+    source_entities: sled::Tree,
+    source_relations: sled::Tree,
+    /// An entity's name as plain words, then its key, for finding mentions
+    /// in text by looking up its word n-grams.
+    mentions: sled::Tree,
+}
+
+impl KnowledgeGraph {
+    pub fn open(path: &Path) -> Result<Self> {
+        let db = sled::open(path).map_err(graph_error)?;
+        let graph = Self {
+            entities: db.open_tree("entities").map_err(graph_error)?,
+            relations: db.open_tree("relations").map_err(graph_error)?,
+            incoming: db.open_tree("incoming").map_err(graph_error)?,
+            extractions: db.open_tree("extractions").map_err(graph_error)?,
+            source_entities: db.open_tree("source_entities").map_err(graph_error)?,
+            source_relations: db.open_tree("source_relations").map_err(graph_error)?,
+            mentions: db.open_tree("mentions").map_err(graph_error)?,
+        };
+        // Graphs built before the lookup trees existed get them filled in once
+        if graph.mentions.is_empty() && !graph.entities.is_empty() {
+            graph.reindex()?;
+        }
+        Ok(graph)
+    }
+
+    /// The extraction `model` made earlier of the chunk with `content_hash`.
+    pub fn cached_extraction(&self, model: &str, content_hash: &str) -> Option<Extraction> {
+        let bytes = self.extractions.get(extraction_key(model, content_hash)).ok().flatten()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    pub fn cache_extraction(&self, model: &str, content_hash: &str, extraction: &Extraction) -> Result<()> {
+        self.extractions
+            .insert(extraction_key(model, content_hash), to_json(extraction)?)
+            .map_err(graph_error)?;
+        Ok(())
+    }
+
+    /// Merges what was extracted from a chunk of `source` into the graph.
+    pub fn add(&self, source: &str, extraction: &Extraction) -> Result<()> {
+        for entity in &extraction.entities {
+            self.add_entity(&entity.name, &entity.kind, source)?;
+        }
+        for relation in &extraction.relations {
+            let (subject, predicate, object) = (
+                clean(&relation.subject),
+                clean(&relation.predicate).to_lowercase(),
+                clean(&relation.object),
+            );
+            if subject.is_empty() || predicate.is_empty() || object.is_empty() {
+                continue;
+            }
+            self.add_entity(&subject, "", source)?;
+            self.add_entity(&object, "", source)?;
+
+            let key = relation_key(&entity_key(&subject), &predicate, &entity_key(&object));
+            let mut stored = match self.relations.get(&key).map_err(graph_error)? {
+                Some(bytes) => from_json::<Relation>(&bytes)?,
+                None => Relation { subject, predicate, object: object.clone(), sources: Vec::new() },
+            };
+            if !stored.sources.iter().any(|s| s == source) {
+                stored.sources.push(source.to_string());
+            }
+            self.relations.insert(&key, to_json(&stored)?).map_err(graph_error)?;
+            self.incoming
+                .insert(prefixed_key(&entity_key(&object), &key), key.as_slice())
+                .map_err(graph_error)?;
+            self.source_relations.insert(prefixed_key(source, &key), &[]).map_err(graph_error)?;
+        }
+        Ok(())
+    }
+
+    /// Drops `source` from every entity and relation, deleting those no
+    /// other source mentions. Returns how many relations were deleted.
+    pub fn remove_source(&self, source: &str) -> Result<usize> {
+        let prefix = node_prefix(source);
+        let mut removed = 0;
+        for entry in self.source_relations.scan_prefix(&prefix).keys() {
+            let index_key = entry.map_err(graph_error)?;
+            let key = &index_key[prefix.len()..];
+            self.source_relations.remove(&index_key).map_err(graph_error)?;
+            let Some(bytes) = self.relations.get(key).map_err(graph_error)? else { continue };
+            let mut relation: Relation = from_json(&bytes)?;
+            relation.sources.retain(|s| s != source);
+            if relation.sources.is_empty() {
+                self.relations.remove(key).map_err(graph_error)?;
+                self.incoming
+                    .remove(prefixed_key(&entity_key(&relation.object), key))
+                    .map_err(graph_error)?;
+                removed += 1;
+            } else {
+                self.relations.insert(key, to_json(&relation)?).map_err(graph_error)?;
+            }
+        }
+        for entry in self.source_entities.scan_prefix(&prefix).keys() {
+            let index_key = entry.map_err(graph_error)?;
+            let key = String::from_utf8_lossy(&index_key[prefix.len()..]).to_string();
+            self.source_entities.remove(&index_key).map_err(graph_error)?;
+            let Some(bytes) = self.entities.get(&key).map_err(graph_error)? else { continue };
+            let mut entity: Entity = from_json(&bytes)?;
+            entity.sources.retain(|s| s != source);
+            if entity.sources.is_empty() {
+                self.entities.remove(&key).map_err(graph_error)?;
+                self.mentions.remove(mention_key(&key)).map_err(graph_error)?;
+            } else {
+                self.entities.insert(key.as_bytes(), to_json(&entity)?).map_err(graph_error)?;
+            }
+        }
+        Ok(removed)
+    }
+
+    pub fn entity(&self, name: &str) -> Result<Option<Entity>> {
+        match self.entities.get(entity_key(name)).map_err(graph_error)? {
+            Some(bytes) => Ok(Some(from_json(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// `name` and the relations it takes part in, or `None` if unknown.
+    pub fn neighbourhood(&self, name: &str) -> Result<Option<Neighbourhood>> {
+        let Some(entity) = self.entity(name)? else {
+            return Ok(None);
+        };
+        let key = entity_key(name);
+
+        let mut outgoing = Vec::new();
+        for entry in self.relations.scan_prefix(node_prefix(&key)) {
+            let (_, bytes) = entry.map_err(graph_error)?;
+            outgoing.push(from_json(&bytes)?);
+        }
+        let mut incoming = Vec::new();
+        for entry in self.incoming.scan_prefix(node_prefix(&key)) {
+            let (_, relation_key) = entry.map_err(graph_error)?;
+            if let Some(bytes) = self.relations.get(relation_key).map_err(graph_error)? {
+                incoming.push(from_json(&bytes)?);
+            }
+        }
+        Ok(Some(Neighbourhood { entity, outgoing, incoming }))
+    }
+
+    /// Known entities whose name appears as whole words in `text`, longest
+    /// names first. Each run of up to [`MAX_MENTION_WORDS`] words is looked
+    /// up, so the cost follows the length of `text`, not the graph's size.
+    pub fn mentioned_in(&self, text: &str) -> Result<Vec<String>> {
+        let text = words(text);
+        let text: Vec<&str> = text.split(' ').filter(|w| !w.is_empty()).collect();
+        let mut found = BTreeSet::new();
+        for start in 0..text.len() {
+            for end in start + 1..=(start + MAX_MENTION_WORDS).min(text.len()) {
+                let prefix = node_prefix(&text[start..end].join(" "));
+                for key in self.mentions.scan_prefix(&prefix).keys() {
+                    let key = key.map_err(graph_error)?;
+                    found.insert(String::from_utf8_lossy(&key[prefix.len()..]).to_string());
+                }
+            }
+        }
+        let mut found: Vec<String> = found.into_iter().collect();
+        found.sort_by_key(|name| std::cmp::Reverse(name.len()));
+        Ok(found)
+    }
+
+    /// Entities whose name contains `text`, most connected first.
+    pub fn search(&self, text: &str, limit: usize) -> Result<Vec<(Entity, usize)>> {
+        let needle = text.trim().to_lowercase();
+        let degrees = self.degrees()?;
+        let mut matches = Vec::new();
+        for entry in self.entities.iter() {
+            let (key, bytes) = entry.map_err(graph_error)?;
+            if String::from_utf8_lossy(&key).contains(&needle) {
+                let degree = degrees.get(key.as_ref()).copied().unwrap_or(0);
+                matches.push((from_json::<Entity>(&bytes)?, degree));
+            }
+        }
+        matches.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.name.cmp(&b.0.name)));
+        matches.truncate(limit);
+        Ok(matches)
+    }
+
+    pub fn stats(&self) -> GraphStats {
+        GraphStats {
+            entities: self.entities.len(),
+            relations: self.relations.len(),
+        }
+    }
+
+    pub fn flush(&self) -> Result<()> {
+        self.relations.flush().map_err(graph_error)?;
+        Ok(())
+    }
+
+    fn add_entity(&self, name: &str, kind: &str, source: &str) -> Result<()> {
+        let name = clean(name);
+        if name.is_empty() {
+            return Ok(());
+        }
+        let key = entity_key(&name);
+        let mut entity = match self.entities.get(&key).map_err(graph_error)? {
+            Some(bytes) => from_json::<Entity>(&bytes)?,
+            None => Entity { name, kind: String::new(), sources: Vec::new() },
+        };
+        if entity.kind.is_empty() {
+            entity.kind = clean(kind).to_lowercase();
+        }
+        if !entity.sources.iter().any(|s| s == source) {
+            entity.sources.push(source.to_string());
+        }
+        self.entities.insert(key.as_bytes(), to_json(&entity)?).map_err(graph_error)?;
+        self.index_entity(&key, source)
+    }
+
+    fn index_entity(&self, key: &str, source: &str) -> Result<()> {
+        self.source_entities.insert(prefixed_key(source, key.as_bytes()), &[]).map_err(graph_error)?;
+        self.mentions.insert(mention_key(key), &[]).map_err(graph_error)?;
+        Ok(())
+    }
+
+    /// Fills the source and mention lookup trees from the entities and
+    /// relations.
+    fn reindex(&self) -> Result<()> {
+        for entry in self.entities.iter() {
+            let (key, bytes) = entry.map_err(graph_error)?;
+            let entity: Entity = from_json(&bytes)?;
+            let key = String::from_utf8_lossy(&key).to_string();
+            for source in &entity.sources {
+                self.index_entity(&key, source)?;
+            }
+        }
+        for entry in self.relations.iter() {
+            let (key, bytes) = entry.map_err(graph_error)?;
+            let relation: Relation = from_json(&bytes)?;
+            for source in &relation.sources {
+                self.source_relations.insert(prefixed_key(source, &key), &[]).map_err(graph_error)?;
+            }
+        }
+        self.flush()
+    }
+
+    /// Relations per entity key, counting both directions.
+    fn degrees(&self) -> Result<BTreeMap<Vec<u8>, usize>> {
+        let mut degrees = BTreeMap::new();
+        for entry in self.relations.iter().values() {
+            let relation: Relation = from_json(&entry.map_err(graph_error)?)?;
+            for name in [&relation.subject, &relation.object] {
+                *degrees.entry(entity_key(name).into_bytes()).or_insert(0) += 1;
+            }
+        }
+        Ok(degrees)
+    }
+}
+
+/// Facts about `neighbourhoods` for the system prompt, one per line, at most
+/// `limit` of them.
+pub fn format_facts(neighbourhoods: &[Neighbourhood], limit: usize) -> String {
+    let mut facts: Vec<String> = Vec::new();
+    for hood in neighbourhoods {
+        if !hood.entity.kind.is_empty() {
+            let fact = format!("{} is a {}", hood.entity.name, hood.entity.kind);
+            if !facts.contains(&fact) {
+                facts.push(fact);
+            }
+        }
+        for relation in hood.outgoing.iter().chain(&hood.incoming) {
+            let fact = relation.describe();
+            if !facts.contains(&fact) {
+                facts.push(fact);
+            }
+        }
+    }
+    facts.truncate(limit);
+    facts.iter().map(|f| format!("- {}", f)).collect::<Vec<_>>().join("\n")
+}
+
+/// Parses the JSON object in a model reply, ignoring any text around it.
+fn parse_extraction(reply: &str) -> Result<Extraction> {
+    let json = match (reply.find('{'), reply.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &reply[start..=end],
+        _ => return Err(AssistantError::KnowledgeError(format!("no JSON in extraction: {:?}", reply))),
+    };
+    serde_json::from_str(json)
+        .map_err(|e| AssistantError::KnowledgeError(format!("unreadable extraction: {}", e)))
+}
+
+fn clean(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").chars().take(MAX_NAME_CHARS).collect()
+}
+
+fn entity_key(name: &str) -> String {
+    clean(name).to_lowercase()
+}
+
+/// Lowercase alphanumeric words of `text` separated by single spaces.
+fn words(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn node_prefix(key: &str) -> Vec<u8> {
+    let mut prefix = key.as_bytes().to_vec();
+    prefix.push(0);
+    prefix
+}
+
+fn relation_key(subject: &str, predicate: &str, object: &str) -> Vec<u8> {
+    let mut key = node_prefix(subject);
+    key.extend_from_slice(predicate.as_bytes());
+    key.push(0);
+    key.extend_from_slice(object.as_bytes());
+    key
+}
+
+/// `node` followed by `key`, for trees scanned by a node, source or name.
+fn prefixed_key(node: &str, key: &[u8]) -> Vec<u8> {
+    let mut prefixed = node_prefix(node);
+    prefixed.extend_from_slice(key);
+    prefixed
+}
+
+fn mention_key(entity_key: &str) -> Vec<u8> {
+    prefixed_key(&words(entity_key), entity_key.as_bytes())
+}
+
+fn extraction_key(model: &str, content_hash: &str) -> Vec<u8> {
+    let mut key = node_prefix(model);
+    key.extend_from_slice(content_hash.as_bytes());
+    key
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    serde_json::to_vec(value).map_err(|e| AssistantError::SerializationError(e.to_string()))
+}
+
+fn from_json<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> Result<T> {
+    serde_json::from_slice(bytes).map_err(|e| AssistantError::SerializationError(e.to_string()))
+}
+
+fn graph_error(e: sled::Error) -> AssistantError {
+    AssistantError::KnowledgeError(format!("knowledge graph: {}", e))
+}
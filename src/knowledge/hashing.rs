@@ -0,0 +1,71 @@
+use super::embeddings::normalize;
+
+/// Vector length used when none is configured.
+pub const DEFAULT_DIMENSION: usize = 512;
+
+const WORD_WEIGHT: f32 = 1.0;
+const BIGRAM_WEIGHT: f32 = 0.7;
+const CHAR_GRAM_WEIGHT: f32 = 0.3;
+const CHAR_GRAM_SIZES: [usize; 2] = [3, 4];
+
+/// Embeds text without a model server by feature hashing.
+///
+/// Words, word bigrams and character 3/4-grams of each word are hashed with
+/// blake3 into a fixed number of signed buckets, and the result is
+/// L2-normalised. It only captures lexical overlap, but it is deterministic
+/// and always available.
+#[derive(Debug, Clone, Copy)]
+pub struct HashingEmbedder {
+    dimension: usize,
+}
+
+impl HashingEmbedder {
+    pub fn new(dimension: usize) -> Self {
+        Self { dimension: dimension.max(1) }
+    }
+
+    /// Name recorded as the vectors' model. It includes the dimension so
+    /// vectors hashed into a different number of buckets are never compared.
+    pub fn model_name(&self) -> String {
+        format!("local-hashing-{}", self.dimension)
+    }
+
+    pub fn embed(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimension];
+        let lowered = text.to_lowercase();
+        let words: Vec<&str> = lowered
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect();
+
+        for word in &words {
+            self.add(&mut vector, "w", word, WORD_WEIGHT);
+
+            let padded: Vec<char> = format!("<{}>", word).chars().collect();
+            for size in CHAR_GRAM_SIZES {
+                for gram in padded.windows(size) {
+                    self.add(&mut vector, "c", &gram.iter().collect::<String>(), CHAR_GRAM_WEIGHT);
+                }
+            }
+        }
+        for pair in words.windows(2) {
+            self.add(&mut vector, "b", &format!("{} {}", pair[0], pair[1]), BIGRAM_WEIGHT);
+        }
+
+        normalize(&mut vector);
+        vector
+    }
+
+    fn add(&self, vector: &mut [f32], kind: &str, feature: &str, weight: f32) {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(kind.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(feature.as_bytes());
+        let hash = hasher.finalize();
+        let bytes = hash.as_bytes();
+
+        let bucket = u64::from_le_bytes(bytes[..8].try_into().expect("8-byte slice")) % self.dimension as u64;
+        let sign = if bytes[8] & 1 == 0 { 1.0 } else { -1.0 };
+        vector[bucket as usize] += sign * weight;
+    }
+}
@@ -0,0 +1,414 @@
+use super::documents::Document;
+use super::embeddings::dot;
+use super::filter::SearchFilter;
+use super::quantization::PreparedQuery;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::ops::Range;
+
+/// Stores with fewer comparable vectors than this are scanned on one thread;
+/// below it, spawning threads costs more than it saves.
+const PARALLEL_MIN_ROWS: usize = 16_384;
+
+/// Vectors from one model and dimension. Full-precision vectors are unit
+/// length and packed row after row so scoring is a linear dot-product scan.
+struct Block {
+    model: String,
+    dim: usize,
+    dense: Vec<f32>,
+    /// Document index of each row of `dense`.
+    dense_rows: Vec<usize>,
+    /// Documents in this block held quantized.
+    quantized_rows: Vec<usize>,
+}
+
+impl Block {
+    fn len(&self) -> usize {
+        self.dense_rows.len() + self.quantized_rows.len()
+    }
+}
+
+/// Where a document's vector is held: a row of a block's dense matrix, or
+/// a slot in its list of quantized rows.
+#[derive(Debug, Clone, Copy)]
+struct Location {
+    block: usize,
+    slot: usize,
+    dense: bool,
+}
+
+/// The store's vectors laid out for brute-force search. Rows refer to
+/// documents by position. Full-precision vectors live only here: indexing a
+/// document takes its `embedding`, and changes are applied row by row as
+/// documents are added, re-embedded and removed.
+#[derive(Default)]
+pub struct VectorIndex {
+    blocks: Vec<Block>,
+    /// Where each document's vector is, by document position.
+    locations: Vec<Option<Location>>,
+}
+
+impl VectorIndex {
+    /// Indexes `documents`, taking their full-precision vectors.
+    pub fn build(documents: &mut [Document]) -> Self {
+        let mut index = Self::default();
+        for doc in documents {
+            index.push(doc);
+        }
+        index
+    }
+
+    /// Indexes `doc`, just appended to the documents.
+    pub fn push(&mut self, doc: &mut Document) {
+        self.locations.push(None);
+        self.set(self.locations.len() - 1, doc);
+    }
+
+    /// Re-indexes the document at `row` after its vector changed, taking
+    /// its full-precision vector. A document without a model or vector is
+    /// left out of searches.
+    pub fn set(&mut self, row: usize, doc: &mut Document) {
+        self.vacate(row);
+        let Some(model) = doc.embedding_model.as_deref() else { return };
+        let dim = match (&doc.embedding, &doc.quantized) {
+            (Some(embedding), _) => embedding.len(),
+            (None, Some(quantized)) => quantized.dim(),
+            (None, None) => return,
+        };
+        let block = match self.blocks.iter().position(|b| b.model == model && b.dim == dim) {
+            Some(i) => i,
+            None => {
+                self.blocks.push(Block {
+                    model: model.to_string(),
+                    dim,
+                    dense: Vec::new(),
+                    dense_rows: Vec::new(),
+                    quantized_rows: Vec::new(),
+                });
+                self.blocks.len() - 1
+            }
+        };
+        let entry = &mut self.blocks[block];
+        let location = match doc.embedding.take() {
+            Some(embedding) => {
+                entry.dense.extend_from_slice(&embedding);
+                entry.dense_rows.push(row);
+                Location { block, slot: entry.dense_rows.len() - 1, dense: true }
+            }
+            None => {
+                entry.quantized_rows.push(row);
+                Location { block, slot: entry.quantized_rows.len() - 1, dense: false }
+            }
+        };
+        self.locations[row] = Some(location);
+    }
+
+    /// Drops the documents at `rows`, in ascending order, and renumbers the
+    /// rest to match the documents once the same positions are removed.
+    pub fn remove(&mut self, rows: &[usize]) {
+        if rows.is_empty() {
+            return;
+        }
+        for &row in rows {
+            self.vacate(row);
+        }
+        let mut removed = rows.iter().peekable();
+        let mut position = 0;
+        self.locations.retain(|_| {
+            let keep = removed.next_if_eq(&&position).is_none();
+            position += 1;
+            keep
+        });
+        for (row, location) in self.locations.iter().enumerate() {
+            if let Some(location) = location {
+                let block = &mut self.blocks[location.block];
+                let rows = if location.dense { &mut block.dense_rows } else { &mut block.quantized_rows };
+                rows[location.slot] = row;
+            }
+        }
+    }
+
+    /// Full-precision vector of the document at `row`, unless it is held
+    /// quantized or not indexed.
+    pub fn vector(&self, row: usize) -> Option<&[f32]> {
+        let location = (*self.locations.get(row)?)?;
+        let block = &self.blocks[location.block];
+        location
+            .dense
+            .then(|| &block.dense[location.slot * block.dim..(location.slot + 1) * block.dim])
+    }
+
+    /// Length of the vector of the document at `row`, whichever form it is held in.
+    pub fn dim(&self, row: usize) -> Option<usize> {
+        let location = (*self.locations.get(row)?)?;
+        Some(self.blocks[location.block].dim)
+    }
+
+    /// Frees `row`'s slot by moving its block's last row into it.
+    fn vacate(&mut self, row: usize) {
+        let Some(location) = self.locations[row].take() else { return };
+        let block = &mut self.blocks[location.block];
+        let moved = if location.dense {
+            let last = block.dense_rows.len() - 1;
+            let dim = block.dim;
+            block.dense.copy_within(last * dim..(last + 1) * dim, location.slot * dim);
+            block.dense.truncate(last * dim);
+            block.dense_rows.swap_remove(location.slot);
+            block.dense_rows.get(location.slot).copied()
+        } else {
+            block.quantized_rows.swap_remove(location.slot);
+            block.quantized_rows.get(location.slot).copied()
+        };
+        if let Some(moved) = moved {
+            self.locations[moved] = Some(location);
+        }
+    }
+
+    /// The `k` best-scoring documents embedded by `model` that pass `filter`,
+    /// as `(score, document index)` pairs, best first. `query` must be unit
+    /// length; full-precision rows then score their cosine similarity.
+    pub fn top_k(
+        &self,
+        documents: &[Document],
+        model: &str,
+        query: &[f32],
+        filter: &SearchFilter,
+        k: usize,
+    ) -> Vec<(f32, usize)> {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        self.top_k_on(threads, documents, model, query, filter, k)
+    }
+
+    /// [`VectorIndex::top_k`] split over up to `threads` threads.
+    fn top_k_on(
+        &self,
+        threads: usize,
+        documents: &[Document],
+        model: &str,
+        query: &[f32],
+        filter: &SearchFilter,
+        k: usize,
+    ) -> Vec<(f32, usize)> {
+        let Some(block) = self.blocks.iter().find(|b| b.model == model && b.dim == query.len()) else {
+            return Vec::new();
+        };
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let prepared = PreparedQuery::new(query);
+        let filtered = !filter.is_empty();
+        let score = |i: usize| -> Option<(f32, usize)> {
+            let dense = i < block.dense_rows.len();
+            let row = if dense { block.dense_rows[i] } else { block.quantized_rows[i - block.dense_rows.len()] };
+            let doc = documents.get(row)?;
+            if filtered && !filter.matches(&doc.metadata) {
+                return None;
+            }
+            let score = if dense {
+                dot(query, &block.dense[i * block.dim..(i + 1) * block.dim])
+            } else {
+                prepared.score(doc.quantized.as_ref()?)
+            };
+            Some((score, row))
+        };
+
+        if block.len() < PARALLEL_MIN_ROWS || threads <= 1 {
+            return scan(0..block.len(), k, &score).into_sorted();
+        }
+
+        let per_thread = block.len().div_ceil(threads);
+        let partials: Vec<TopK> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..block.len())
+                .step_by(per_thread)
+                .map(|start| {
+                    let range = start..(start + per_thread).min(block.len());
+                    let score = &score;
+                    scope.spawn(move || scan(range, k, score))
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().expect("scan thread panicked")).collect()
+        });
+
+        let mut merged = TopK::new(k);
+        for partial in partials {
+            for Reverse(hit) in partial.heap {
+                merged.push(hit.0, hit.1);
+            }
+        }
+        merged.into_sorted()
+    }
+}
+
+fn scan(range: Range<usize>, k: usize, score: &(impl Fn(usize) -> Option<(f32, usize)> + Sync)) -> TopK {
+    let mut top = TopK::new(k);
+    for i in range {
+        if let Some((score, row)) = score(i) {
+            top.push(score, row);
+        }
+    }
+    top
+}
+
+/// A score and document index, ordered by score.
+struct Hit(f32, usize);
+
+impl PartialEq for Hit {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Hit {}
+
+impl PartialOrd for Hit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Hit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(other.1.cmp(&self.1))
+    }
+}
+
+/// The `k` highest-scoring hits seen so far, kept in a min-heap so each
+/// new hit costs at most `log k`. Ties go to the lower row, so the result
+/// doesn't depend on the order hits arrive in.
+struct TopK {
+    k: usize,
+    heap: BinaryHeap<Reverse<Hit>>,
+}
+
+impl TopK {
+    fn new(k: usize) -> Self {
+        Self { k, heap: BinaryHeap::with_capacity(k + 1) }
+    }
+
+    fn push(&mut self, score: f32, row: usize) {
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(Hit(score, row)));
+        } else if self.heap.peek().is_some_and(|Reverse(worst)| Hit(score, row) > *worst) {
+            self.heap.pop();
+            self.heap.push(Reverse(Hit(score, row)));
+        }
+    }
+
+    fn into_sorted(self) -> Vec<(f32, usize)> {
+        self.heap.into_sorted_vec().into_iter().map(|Reverse(hit)| (hit.0, hit.1)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(model: &str, vector: Vec<f32>) -> Document {
+        let mut doc = Document::new(format!("{:?}", vector), "test".to_string()).with_embedding(vector);
+        doc.embedding_model = Some(model.to_string());
+        doc
+    }
+
+    fn basis(dim: usize, axis: usize, weight: f32) -> Vec<f32> {
+        let mut vector = vec![0.0; dim];
+        vector[axis] = weight;
+        vector
+    }
+
+    #[test]
+    fn top_k_keeps_the_best_in_order() {
+        let mut top = TopK::new(3);
+        for (score, row) in [(0.1, 0), (0.9, 1), (0.5, 2), (0.7, 3), (0.2, 4)] {
+            top.push(score, row);
+        }
+        assert_eq!(top.into_sorted(), vec![(0.9, 1), (0.7, 3), (0.5, 2)]);
+    }
+
+    #[test]
+    fn top_k_breaks_ties_by_row() {
+        let mut top = TopK::new(2);
+        for row in [4, 1, 3] {
+            top.push(0.5, row);
+        }
+        assert_eq!(top.into_sorted(), vec![(0.5, 1), (0.5, 3)]);
+    }
+
+    #[test]
+    fn build_takes_vectors_and_searches_by_model() {
+        let mut documents = vec![
+            document("a", basis(3, 0, 1.0)),
+            document("b", basis(3, 0, 1.0)),
+            document("a", basis(3, 1, 1.0)),
+        ];
+        let index = VectorIndex::build(&mut documents);
+        assert!(documents.iter().all(|doc| doc.embedding.is_none()));
+        assert_eq!(index.vector(2), Some(&[0.0, 1.0, 0.0][..]));
+
+        let hits = index.top_k(&documents, "a", &basis(3, 0, 1.0), &SearchFilter::default(), 5);
+        assert_eq!(hits, vec![(1.0, 0), (0.0, 2)]);
+        assert!(index.top_k(&documents, "a", &[1.0, 0.0], &SearchFilter::default(), 5).is_empty());
+    }
+
+    #[test]
+    fn remove_renumbers_the_remaining_rows() {
+        let mut documents: Vec<Document> = (0..5).map(|i| document("a", basis(5, i, 1.0))).collect();
+        let mut index = VectorIndex::build(&mut documents);
+
+        index.remove(&[0, 2]);
+        let mut row = 0;
+        documents.retain(|_| {
+            row += 1;
+            row != 1 && row != 3
+        });
+
+        assert_eq!(index.vector(0), Some(&basis(5, 1, 1.0)[..]));
+        assert_eq!(index.vector(1), Some(&basis(5, 3, 1.0)[..]));
+        assert_eq!(index.vector(2), Some(&basis(5, 4, 1.0)[..]));
+        assert_eq!(index.vector(3), None);
+        let hits = index.top_k(&documents, "a", &basis(5, 4, 1.0), &SearchFilter::default(), 1);
+        assert_eq!(hits, vec![(1.0, 2)]);
+    }
+
+    #[test]
+    fn set_moves_a_row_between_models() {
+        let mut documents = vec![document("a", basis(2, 0, 1.0)), document("a", basis(2, 1, 1.0))];
+        let mut index = VectorIndex::build(&mut documents);
+
+        documents[0].embedding = Some(basis(2, 1, 1.0));
+        documents[0].embedding_model = Some("b".to_string());
+        index.set(0, &mut documents[0]);
+
+        let query = basis(2, 1, 1.0);
+        assert_eq!(index.top_k(&documents, "a", &query, &SearchFilter::default(), 5), vec![(1.0, 1)]);
+        assert_eq!(index.top_k(&documents, "b", &query, &SearchFilter::default(), 5), vec![(1.0, 0)]);
+    }
+
+    #[test]
+    fn threaded_scan_matches_a_single_thread() {
+        let dim = 8;
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut documents: Vec<Document> = (0..PARALLEL_MIN_ROWS + 1_000)
+            .map(|_| {
+                let vector = (0..dim)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 7;
+                        state ^= state << 17;
+                        (state % 2_000) as f32 / 1_000.0 - 1.0
+                    })
+                    .collect();
+                document("a", vector)
+            })
+            .collect();
+        let index = VectorIndex::build(&mut documents);
+        let query = basis(dim, 3, 1.0);
+        let filter = SearchFilter::default();
+
+        let single = index.top_k_on(1, &documents, "a", &query, &filter, 25);
+        assert_eq!(single.len(), 25);
+        for threads in [2, 3, 7] {
+            assert_eq!(index.top_k_on(threads, &documents, "a", &query, &filter, 25), single);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use super::embeddings::{Embedding, EmbeddingService};
+use crate::{AssistantError, Result};
+use indicatif::ProgressBar;
+use std::future::Future;
+use tokio::task::JoinSet;
+
+/// Embeds `texts` with at most `concurrency` requests in flight, advancing
+/// `progress` by one per finished text. Results are in input order; a text
+/// that failed to embed gets its error rather than failing the others.
+pub async fn embed_concurrently(
+    service: &EmbeddingService,
+    texts: Vec<String>,
+    concurrency: usize,
+    progress: &ProgressBar,
+) -> Result<Vec<Result<Embedding>>> {
+    map_concurrently(texts, concurrency, progress, |text| {
+        let service = service.clone();
+        async move { service.embed(&text).await }
+    })
+    .await
+}
+
+/// Runs `task` over `items` with at most `concurrency` in flight, advancing
+/// `progress` by one per finished item. Results are in input order.
+pub async fn map_concurrently<T, R, F, Fut>(
+    items: Vec<T>,
+    concurrency: usize,
+    progress: &ProgressBar,
+    task: F,
+) -> Result<Vec<R>>
+where
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = R> + Send + 'static,
+    R: Send + 'static,
+{
+    let mut results: Vec<Option<R>> = (0..items.len()).map(|_| None).collect();
+    let mut queue = items.into_iter().enumerate();
+    let mut tasks = JoinSet::new();
+
+    loop {
+        while tasks.len() < concurrency.max(1) {
+            let Some((i, item)) = queue.next() else {
+                break;
+            };
+            let future = task(item);
+            tasks.spawn(async move { (i, future.await) });
+        }
+        let Some(joined) = tasks.join_next().await else {
+            break;
+        };
+        let (i, result) = joined.map_err(|e| AssistantError::KnowledgeError(e.to_string()))?;
+        results[i] = Some(result);
+        progress.inc(1);
+    }
+
+    Ok(results.into_iter().map(|r| r.expect("every item is processed")).collect())
+}
@@ -0,0 +1,107 @@
+use super::documents::Document;
+use crate::{AssistantError, Result};
+use std::borrow::Borrow;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// What to do with an imported chunk whose content is already in the store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    Skip,
+    Replace,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub replaced: usize,
+    pub skipped: usize,
+    /// Imported chunks whose vectors were missing or from another model.
+    pub reembedded: usize,
+}
+
+/// Knowledge interchange formats, chosen by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A single JSON array, as written by the original `export_knowledge`.
+    Json,
+    /// One document per line, streamed without holding the whole file.
+    Jsonl,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("jsonl") || ext.eq_ignore_ascii_case("ndjson") => Self::Jsonl,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// Writes `documents` to `path`, optionally stripping their embeddings.
+pub fn write_documents<D: Borrow<Document>>(
+    path: &Path,
+    documents: impl Iterator<Item = D>,
+    include_embeddings: bool,
+) -> Result<usize> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let format = Format::from_path(path);
+    let mut count = 0;
+    
+    if format == Format::Json {
+        writer.write_all(b"[\n")?;
+    }
+    for doc in documents {
+        let doc = doc.borrow();
+        let stripped;
+        let doc = if include_embeddings {
+            doc
+        } else {
+            stripped = Document { embedding: None, embedding_model: None, quantized: None, ..doc.clone() };
+            &stripped
+        };
+        
+        if format == Format::Json && count > 0 {
+            writer.write_all(b",\n")?;
+        }
+        serde_json::to_writer(&mut writer, doc)
+            .map_err(|e| AssistantError::SerializationError(e.to_string()))?;
+        if format == Format::Jsonl {
+            writer.write_all(b"\n")?;
+        }
+        count += 1;
+    }
+    if format == Format::Json {
+        writer.write_all(b"\n]\n")?;
+    }
+    
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Reads documents from `path`. JSONL files are parsed lazily line by line;
+/// JSON arrays are read in one go.
+pub fn read_documents(path: &Path) -> Result<Box<dyn Iterator<Item = Result<Document>>>> {
+    let reader = BufReader::new(File::open(path)?);
+    
+    match Format::from_path(path) {
+        Format::Jsonl => Ok(Box::new(
+            reader
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| line.as_ref().map(|l| !l.trim().is_empty()).unwrap_or(true))
+                .map(|(i, line)| {
+                    let line = line?;
+                    serde_json::from_str(&line).map_err(|e| {
+                        AssistantError::SerializationError(format!("line {}: {}", i + 1, e))
+                    })
+                }),
+        )),
+        Format::Json => {
+            let documents: Vec<Document> = serde_json::from_reader(reader)
+                .map_err(|e| AssistantError::SerializationError(e.to_string()))?;
+            Ok(Box::new(documents.into_iter().map(Ok)))
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{AssistantError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Store-level record of which embedding space the vectors live in, kept
+/// next to the documents file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoreManifest {
+    /// Model every vector in the store is meant to come from.
+    pub embedding_model: String,
+    /// Vector length produced by `embedding_model`, once known.
+    #[serde(default)]
+    pub dimension: Option<usize>,
+    pub updated_at: DateTime<Utc>,
+    /// Ids of chunks still to be re-embedded with `embedding_model` itself,
+    /// as when it was re-pulled. Their old vectors stay searchable meanwhile.
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    pub reembed_pending: HashSet<String>,
+}
+
+impl StoreManifest {
+    pub fn new(embedding_model: String, dimension: Option<usize>) -> Self {
+        Self {
+            embedding_model,
+            dimension,
+            updated_at: Utc::now(),
+            reembed_pending: HashSet::new(),
+        }
+    }
+
+    /// `documents.json` -> `documents.manifest.json`
+    pub fn path_for(storage_path: &Path) -> PathBuf {
+        storage_path.with_extension("manifest.json")
+    }
+
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = std::fs::read_to_string(path)?;
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| AssistantError::SerializationError(e.to_string()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| AssistantError::SerializationError(e.to_string()))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// How the store's vectors relate to the configured embedding model.
+#[derive(Debug, Clone)]
+pub struct EmbeddingStatus {
+    pub store_model: String,
+    pub configured_model: String,
+    pub dimension: Option<usize>,
+    /// Chunks whose vectors came from a model other than `store_model`, or
+    /// that are waiting to be re-embedded with it.
+    pub stale: usize,
+    pub total: usize,
+}
+
+impl EmbeddingStatus {
+    /// The configured model differs from the one the store was built with.
+    pub fn is_mismatched(&self) -> bool {
+        self.store_model != self.configured_model
+    }
+
+    /// A re-embed has been started and not yet finished.
+    pub fn is_migrating(&self) -> bool {
+        !self.is_mismatched() && self.stale > 0
+    }
+
+    /// Status across several stores. A store still on another model is
+    /// reported as the store model, so a mismatch anywhere shows.
+    pub fn combine(configured_model: &str, statuses: &[EmbeddingStatus]) -> Self {
+        Self {
+            store_model: statuses
+                .iter()
+                .find(|s| s.is_mismatched())
+                .map(|s| s.store_model.clone())
+                .unwrap_or_else(|| configured_model.to_string()),
+            configured_model: configured_model.to_string(),
+            dimension: statuses.iter().find_map(|s| s.dimension),
+            stale: statuses.iter().map(|s| s.stale).sum(),
+            total: statuses.iter().map(|s| s.total).sum(),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use super::embeddings::cosine_similarity;
+use super::vectorstore::ScoredDocument;
+
+/// Greedy Maximal Marginal Relevance selection.
+///
+/// Repeatedly picks the candidate maximising
+/// `lambda * sim(query, doc) - (1 - lambda) * max sim(doc, already picked)`,
+/// so near-duplicates of earlier picks are pushed down. `lambda = 1.0` keeps
+/// the original relevance order; lower values favour diversity. Candidates
+/// without an embedding are treated as dissimilar to everything.
+pub fn mmr_select(mut candidates: Vec<ScoredDocument>, k: usize, lambda: f32) -> Vec<ScoredDocument> {
+    let mut selected: Vec<ScoredDocument> = Vec::with_capacity(k.min(candidates.len()));
+    
+    while selected.len() < k && !candidates.is_empty() {
+        let best = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, candidate)| {
+                let redundancy = selected
+                    .iter()
+                    .filter_map(|picked| similarity(candidate, picked))
+                    .fold(0.0f32, f32::max);
+                (i, lambda * candidate.score - (1.0 - lambda) * redundancy)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i);
+        
+        match best {
+            Some(i) => selected.push(candidates.swap_remove(i)),
+            None => break,
+        }
+    }
+    
+    selected
+}
+
+fn similarity(a: &ScoredDocument, b: &ScoredDocument) -> Option<f32> {
+    let a = a.document.embedding.as_ref()?;
+    let b = b.document.embedding.as_ref()?;
+    Some(cosine_similarity(a, b))
+}
@@ -1,3 +1,20 @@
+pub mod cache;
+pub mod collections;
+pub mod decay;
 pub mod documents;
 pub mod embeddings;
+pub mod expansion;
+pub mod extractors;
+pub mod filter;
+pub mod fusion;
+pub mod graph;
+pub mod hashing;
+pub mod index;
+pub mod ingest;
+pub mod interchange;
+pub mod manifest;
+pub mod mmr;
+pub mod quantization;
+pub mod rerank;
 pub mod vectorstore;
+pub mod watcher;
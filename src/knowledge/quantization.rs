@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+
+/// How stored vectors are compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Quantization {
+    /// Full `f32` vectors.
+    None,
+    /// One signed byte per dimension plus a per-vector scale (4x smaller).
+    Int8,
+    /// One sign bit per dimension (32x smaller, coarse).
+    Binary,
+}
+
+impl Quantization {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "none" | "off" | "f32" | "" => Some(Self::None),
+            "int8" | "i8" | "scalar" => Some(Self::Int8),
+            "binary" | "bit" | "1bit" => Some(Self::Binary),
+            _ => None,
+        }
+    }
+}
+
+/// A compressed embedding, serialized with its codes hex-encoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum QuantizedVector {
+    Int8 {
+        /// Multiply a code by this to recover the original value.
+        scale: f32,
+        /// Magnitude of the original vector.
+        norm: f32,
+        #[serde(with = "hex_codes")]
+        codes: Vec<u8>,
+    },
+    Binary {
+        dim: usize,
+        #[serde(with = "hex_codes")]
+        bits: Vec<u8>,
+    },
+}
+
+impl QuantizedVector {
+    /// Compresses `vector`, or returns `None` for [`Quantization::None`].
+    pub fn encode(vector: &[f32], quantization: Quantization) -> Option<Self> {
+        match quantization {
+            Quantization::None => None,
+            Quantization::Int8 => {
+                let max = vector.iter().fold(0.0f32, |m, x| m.max(x.abs()));
+                let scale = if max > 0.0 { max / 127.0 } else { 1.0 };
+                let codes = vector
+                    .iter()
+                    .map(|x| (x / scale).round().clamp(-127.0, 127.0) as i8 as u8)
+                    .collect();
+                Some(Self::Int8 { scale, norm: norm(vector), codes })
+            }
+            Quantization::Binary => Some(Self::Binary {
+                dim: vector.len(),
+                bits: pack_signs(vector),
+            }),
+        }
+    }
+
+    pub fn dim(&self) -> usize {
+        match self {
+            Self::Int8 { codes, .. } => codes.len(),
+            Self::Binary { dim, .. } => *dim,
+        }
+    }
+
+    /// Bytes held in memory for this vector.
+    pub fn size_bytes(&self) -> usize {
+        match self {
+            Self::Int8 { codes, .. } => codes.len() + 2 * std::mem::size_of::<f32>(),
+            Self::Binary { bits, .. } => bits.len() + std::mem::size_of::<usize>(),
+        }
+    }
+
+    /// Approximate reconstruction; binary vectors come back as unit-length
+    /// sign vectors.
+    pub fn dequantize(&self) -> Vec<f32> {
+        match self {
+            Self::Int8 { scale, codes, .. } => codes.iter().map(|&c| c as i8 as f32 * scale).collect(),
+            Self::Binary { dim, bits } => {
+                let magnitude = 1.0 / (*dim as f32).sqrt();
+                (0..*dim)
+                    .map(|i| if bits[i / 8] & (1 << (i % 8)) != 0 { magnitude } else { -magnitude })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// A query vector prepared once for scoring against many quantized vectors.
+pub struct PreparedQuery<'a> {
+    values: &'a [f32],
+    norm: f32,
+    bits: Vec<u8>,
+}
+
+impl<'a> PreparedQuery<'a> {
+    pub fn new(values: &'a [f32]) -> Self {
+        Self {
+            values,
+            norm: norm(values),
+            bits: pack_signs(values),
+        }
+    }
+
+    /// Approximate cosine similarity between the query and `vector`.
+    pub fn score(&self, vector: &QuantizedVector) -> f32 {
+        if vector.dim() != self.values.len() {
+            return 0.0;
+        }
+        match vector {
+            QuantizedVector::Int8 { scale, norm, codes } => {
+                if self.norm == 0.0 || *norm == 0.0 {
+                    return 0.0;
+                }
+                let dot = dot_codes(self.values, codes);
+                dot * scale / (self.norm * norm)
+            }
+            QuantizedVector::Binary { dim, bits } => {
+                // Angle estimate from the fraction of differing sign bits
+                let differing: u32 = self.bits.iter().zip(bits).map(|(a, b)| (a ^ b).count_ones()).sum();
+                (std::f32::consts::PI * differing as f32 / *dim as f32).cos()
+            }
+        }
+    }
+}
+
+/// Dot product of a float query with int8 codes, summed in independent
+/// lanes so it vectorizes like [`crate::knowledge::embeddings::dot`].
+fn dot_codes(values: &[f32], codes: &[u8]) -> f32 {
+    const LANES: usize = 8;
+    let mut sums = [0.0f32; LANES];
+    let (value_chunks, code_chunks) = (values.chunks_exact(LANES), codes.chunks_exact(LANES));
+    let tail: f32 = value_chunks
+        .remainder()
+        .iter()
+        .zip(code_chunks.remainder())
+        .map(|(q, &c)| q * (c as i8 as f32))
+        .sum();
+    for (q, c) in value_chunks.zip(code_chunks) {
+        for lane in 0..LANES {
+            sums[lane] += q[lane] * (c[lane] as i8 as f32);
+        }
+    }
+    sums.iter().sum::<f32>() + tail
+}
+
+fn norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+fn pack_signs(vector: &[f32]) -> Vec<u8> {
+    let mut bits = vec![0u8; vector.len().div_ceil(8)];
+    for (i, x) in vector.iter().enumerate() {
+        if *x > 0.0 {
+            bits[i / 8] |= 1 << (i % 8);
+        }
+    }
+    bits
+}
+
+/// How much memory the store's vectors take compared to keeping them all as
+/// full `f32` vectors.
+#[derive(Debug, Clone)]
+pub struct VectorMemory {
+    pub quantization: Quantization,
+    pub vectors: usize,
+    pub in_memory_bytes: usize,
+    pub full_precision_bytes: usize,
+    /// Whether full vectors are kept on disk for rescoring.
+    pub full_on_disk: bool,
+}
+
+impl VectorMemory {
+    pub fn saved_bytes(&self) -> usize {
+        self.full_precision_bytes.saturating_sub(self.in_memory_bytes)
+    }
+}
+
+pub(crate) fn vector_to_bytes(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+pub(crate) fn vector_from_bytes(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+/// Serializes byte codes as a hex string rather than a JSON number array.
+mod hex_codes {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::fmt::Write;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut hex = String::with_capacity(bytes.len() * 2);
+        for byte in bytes {
+            let _ = write!(hex, "{:02x}", byte);
+        }
+        serializer.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        if !hex.is_ascii() {
+            return Err(serde::de::Error::custom("non-ASCII character in hex string"));
+        }
+        if hex.len() % 2 != 0 {
+            return Err(serde::de::Error::custom("odd-length hex string"));
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
@@ -0,0 +1,181 @@
+use super::ingest::map_concurrently;
+use super::vectorstore::ScoredDocument;
+use crate::llm::ollama::OllamaClient;
+use crate::{AssistantError, Result};
+use indicatif::ProgressBar;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// How retrieval candidates are reordered after the vector search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RerankMode {
+    Off,
+    /// A dedicated reranker model served by Ollama, prompted with the raw
+    /// `query`/`document` pair and expected to reply with a relevance score.
+    Model,
+    /// A chat model asked to grade each passage from 0 to 10.
+    Judge,
+}
+
+impl RerankMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "off" | "none" | "" => Some(Self::Off),
+            "model" | "reranker" => Some(Self::Model),
+            "judge" | "llm" => Some(Self::Judge),
+            _ => None,
+        }
+    }
+}
+
+/// Latency and outcome of one rerank pass.
+#[derive(Debug, Clone)]
+pub struct RerankReport {
+    pub mode: RerankMode,
+    pub candidates: usize,
+    pub elapsed: Duration,
+    /// Set when scoring failed and the original order was kept.
+    pub error: Option<String>,
+}
+
+/// Second retrieval stage that rescores candidates pointwise with a model.
+pub struct Reranker {
+    ollama: OllamaClient,
+    mode: RerankMode,
+    model: String,
+    /// Candidates scored at once, so a large shortlist doesn't flood Ollama.
+    concurrency: usize,
+}
+
+impl Reranker {
+    pub fn new(ollama: OllamaClient, mode: RerankMode, model: String, concurrency: usize) -> Self {
+        Self { ollama, mode, model, concurrency }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.mode != RerankMode::Off
+    }
+
+    /// Reorders `candidates` by model-assigned relevance, replacing their
+    /// scores with values normalised to 0..1. If any candidate can't be
+    /// scored the original order is returned and the report carries the error.
+    pub async fn rerank(
+        &self,
+        query: &str,
+        candidates: Vec<ScoredDocument>,
+    ) -> (Vec<ScoredDocument>, RerankReport) {
+        let started = Instant::now();
+        let mut report = RerankReport {
+            mode: self.mode,
+            candidates: candidates.len(),
+            elapsed: Duration::ZERO,
+            error: None,
+        };
+        if !self.is_enabled() {
+            return (candidates, report);
+        }
+
+        let result = self.score_all(query, &candidates).await;
+        let reranked = match result {
+            Ok(scores) => {
+                let mut reranked: Vec<ScoredDocument> = candidates
+                    .into_iter()
+                    .zip(normalise(scores))
+                    .map(|(hit, score)| ScoredDocument { score, ..hit })
+                    .collect();
+                reranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+                reranked
+            }
+            Err(e) => {
+                report.error = Some(e.to_string());
+                candidates
+            }
+        };
+        report.elapsed = started.elapsed();
+        (reranked, report)
+    }
+
+    async fn score_all(&self, query: &str, candidates: &[ScoredDocument]) -> Result<Vec<f32>> {
+        let prompts: Vec<(String, bool)> = candidates
+            .iter()
+            .map(|candidate| self.prompt(query, &candidate.document.content))
+            .collect();
+        let scores = map_concurrently(prompts, self.concurrency, &ProgressBar::hidden(), |(prompt, raw)| {
+            let ollama = self.ollama.clone();
+            let model = self.model.clone();
+            async move {
+                let reply = ollama.generate(&model, &prompt, raw, 0.0).await?;
+                parse_score(&reply)
+            }
+        })
+        .await?;
+        scores.into_iter().collect()
+    }
+
+    fn prompt(&self, query: &str, passage: &str) -> (String, bool) {
+        match self.mode {
+            RerankMode::Model => (format!("query: {}\ndocument: {}\nrelevance:", query, passage), true),
+            _ => (
+                format!(
+                    "Rate how useful the passage is for answering the question, \
+                    from 0 (irrelevant) to 10 (directly answers it). \
+                    Reply with the number only.\n\n\
+                    Question: {}\n\nPassage:\n{}\n\nScore:",
+                    query, passage
+                ),
+                false,
+            ),
+        }
+    }
+}
+
+fn parse_score(reply: &str) -> Result<f32> {
+    static NUMBER: OnceLock<Regex> = OnceLock::new();
+    let number = NUMBER.get_or_init(|| Regex::new(r"-?\d+(?:\.\d+)?").expect("valid regex"));
+    number
+        .find(reply)
+        .and_then(|m| m.as_str().parse().ok())
+        .ok_or_else(|| AssistantError::KnowledgeError(format!("reranker gave no score: {:?}", reply)))
+}
+
+/// Min-max scales scores to 0..1 so they are comparable with cosine similarity.
+fn normalise(scores: Vec<f32>) -> Vec<f32> {
+    let min = scores.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    if max - min <= f32::EPSILON {
+        return vec![1.0; scores.len()];
+    }
+    scores.into_iter().map(|s| (s - min) / (max - min)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_score_takes_the_first_number() {
+        assert_eq!(parse_score("8/10").unwrap(), 8.0);
+        assert_eq!(parse_score("Score: -1.5").unwrap(), -1.5);
+        assert_eq!(parse_score(" 7.25\n").unwrap(), 7.25);
+    }
+
+    #[test]
+    fn parse_score_rejects_a_reply_without_a_number() {
+        assert!(parse_score("very relevant").is_err());
+        assert!(parse_score("").is_err());
+    }
+
+    #[test]
+    fn normalise_scales_to_unit_range() {
+        assert_eq!(normalise(vec![2.0, 10.0, 6.0]), vec![0.0, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn normalise_handles_equal_and_empty_scores() {
+        assert_eq!(normalise(vec![4.0, 4.0, 4.0]), vec![1.0, 1.0, 1.0]);
+        assert!(normalise(Vec::new()).is_empty());
+    }
+}
@@ -1,58 +1,574 @@
-use super::documents::Document;
-use super::embeddings::{EmbeddingService, cosine_similarity};
+use super::documents::{Document, content_hash, detect_language};
+use super::embeddings::{Embedding, EmbeddingService, cosine_similarity, normalize};
+use super::decay::TimeDecay;
+use super::filter::SearchFilter;
+use super::index::VectorIndex;
+use super::interchange::{ConflictPolicy, ImportReport};
+use super::manifest::{EmbeddingStatus, StoreManifest};
+use super::mmr::mmr_select;
+use super::quantization::{
+    vector_from_bytes, vector_to_bytes, Quantization, QuantizedVector, VectorMemory,
+};
 use crate::AssistantError;
-use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Serializer};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// With quantized vectors or time decay, this many times the candidate pool
+/// is shortlisted and rescored, at full precision or for recency.
+const RESCORE_FACTOR: usize = 4;
+
+/// Outcome of syncing one source into the store.
+#[derive(Debug, Clone, Default)]
+pub struct SyncReport {
+    pub added: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+impl SyncReport {
+    pub fn is_noop(&self) -> bool {
+        self.added == 0 && self.removed == 0
+    }
+}
+
+/// A search hit together with its cosine similarity to the query.
+#[derive(Debug, Clone)]
+pub struct ScoredDocument {
+    pub document: Document,
+    pub score: f32,
+}
+
+/// What was learned from one source.
+#[derive(Debug, Clone)]
+pub struct SourceSummary {
+    pub source: String,
+    pub chunks: usize,
+    /// When the oldest and newest of its chunks were learned.
+    pub first_learned: DateTime<Utc>,
+    pub last_learned: DateTime<Utc>,
+}
+
+/// Store-level figures for inspection.
+#[derive(Debug, Clone)]
+pub struct StoreStats {
+    pub chunks: usize,
+    pub sources: usize,
+    pub embedding_model: String,
+    pub dimension: Option<usize>,
+    pub quantization: Quantization,
+    /// Documents file, manifest and full-vector index together.
+    pub bytes_on_disk: u64,
+    pub oldest: Option<DateTime<Utc>>,
+    pub newest: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    pub k: usize,
+    /// Hits scoring below this similarity are dropped.
+    pub min_score: f32,
+    pub filter: SearchFilter,
+    /// Relevance/diversity trade-off for MMR re-ranking; 1.0 disables it.
+    pub mmr_lambda: f32,
+    /// Size of the candidate pool MMR picks `k` results from.
+    pub fetch_k: usize,
+}
+
+impl SearchOptions {
+    /// Top `k` over everything, with no similarity cutoff.
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            min_score: -1.0,
+            filter: SearchFilter::default(),
+            mmr_lambda: 1.0,
+            fetch_k: k,
+        }
+    }
+}
 
 pub struct VectorStore {
     documents: Vec<Document>,
     storage_path: PathBuf,
     embedding_service: EmbeddingService,
+    manifest: StoreManifest,
+    quantization: Quantization,
+    /// Full-precision vectors kept on disk while the in-memory ones are quantized.
+    full_vectors: Option<sled::Db>,
+    keep_full: bool,
+    index: VectorIndex,
+    decay: Option<TimeDecay>,
 }
 
 impl VectorStore {
     pub fn new(storage_path: PathBuf, embedding_service: EmbeddingService) -> Self {
+        let manifest = StoreManifest::new(embedding_service.model().to_string(), None);
         let mut store = Self {
             documents: Vec::new(),
             storage_path,
             embedding_service,
+            manifest,
+            quantization: Quantization::None,
+            full_vectors: None,
+            keep_full: false,
+            index: VectorIndex::default(),
+            decay: None,
         };
         
         let _ = store.load();
+        let _ = store.load_manifest();
+        store.index = VectorIndex::build(&mut store.documents);
         store
     }
     
-    pub async fn add_document(&mut self, mut doc: Document) -> Result<(), AssistantError> {
-        let embedding = self.embedding_service.embed_text(&doc.content).await?;
-        doc.embedding = Some(embedding);
-        self.documents.push(doc);
-        self.save()?;
-        Ok(())
+    /// Holds vectors compressed with `quantization`, converting any stored in
+    /// another form. With `keep_full` the full-precision vectors are moved to
+    /// an on-disk index next to the store and used to rescore top candidates.
+    pub fn with_quantization(mut self, quantization: Quantization, keep_full: bool) -> Result<Self, AssistantError> {
+        self.quantization = quantization;
+        self.keep_full = keep_full && quantization != Quantization::None;
+        
+        let path = full_vectors_path(&self.storage_path);
+        if self.keep_full || path.exists() {
+            self.full_vectors = Some(sled::open(&path).map_err(sled_error)?);
+        }
+        
+        let mut changed = false;
+        for i in 0..self.documents.len() {
+            changed |= self.convert_vector(i)?;
+        }
+        if changed {
+            self.save()?;
+        }
+        self.prune_full_vectors()?;
+        Ok(self)
     }
     
-    pub async fn add_documents(&mut self, docs: Vec<Document>) -> Result<(), AssistantError> {
-        for doc in docs {
-            self.add_document(doc).await?;
+    /// Blends similarity with chunk age in searches; `None` ranks by
+    /// similarity alone.
+    pub fn set_time_decay(&mut self, decay: Option<TimeDecay>) {
+        self.decay = decay;
+    }
+    
+    /// Memory taken by the in-memory vectors against keeping them all at full precision.
+    pub fn vector_memory(&self) -> VectorMemory {
+        let mut memory = VectorMemory {
+            quantization: self.quantization,
+            vectors: 0,
+            in_memory_bytes: 0,
+            full_precision_bytes: 0,
+            full_on_disk: self.keep_full,
+        };
+        for (row, doc) in self.documents.iter().enumerate() {
+            let Some(dim) = self.index.dim(row) else { continue };
+            memory.vectors += 1;
+            memory.full_precision_bytes += dim * std::mem::size_of::<f32>();
+            memory.in_memory_bytes += match (self.index.vector(row), &doc.quantized) {
+                (Some(vector), _) => std::mem::size_of_val(vector),
+                (None, Some(quantized)) => quantized.size_bytes(),
+                (None, None) => 0,
+            };
+        }
+        memory
+    }
+    
+    /// Every chunk with its full-precision vector, read back from disk when
+    /// it is held quantized and the full vector was kept.
+    pub fn documents_with_vectors(&self) -> impl Iterator<Item = Cow<'_, Document>> {
+        (0..self.documents.len()).map(|row| {
+            let doc = self.stored_document(row);
+            if doc.embedding.is_some() {
+                return doc;
+            }
+            match self.full_vector(&doc.id) {
+                Some(embedding) => Cow::Owned(Document { embedding: Some(embedding), ..doc.into_owned() }),
+                None => doc,
+            }
+        })
+    }
+    
+    /// How the stored vectors relate to the configured embedding model.
+    pub fn embedding_status(&self) -> EmbeddingStatus {
+        let configured = self.embedding_service.model();
+        EmbeddingStatus {
+            store_model: self.manifest.embedding_model.clone(),
+            configured_model: configured.to_string(),
+            dimension: self.manifest.dimension,
+            stale: (0..self.documents.len()).filter(|&row| !self.is_current(row)).count(),
+            total: self.documents.len(),
+        }
+    }
+    
+    /// Switches the store over to the configured embedding model. Existing
+    /// chunks are then migrated by [`VectorStore::reembed_batch`].
+    pub fn begin_reembed(&mut self) -> Result<EmbeddingStatus, AssistantError> {
+        // Re-embedding with the same model (e.g. after it was re-pulled) redoes
+        // everything, keeping the old vectors searchable until each is replaced
+        let same_model = self.manifest.embedding_model == self.embedding_service.model();
+        if same_model {
+            self.embedding_service.clear_cache()?;
+        }
+        self.manifest = StoreManifest::new(self.embedding_service.model().to_string(), None);
+        if same_model {
+            self.manifest.reembed_pending = self.documents.iter().map(|d| d.id.clone()).collect();
+        }
+        self.save_manifest()?;
+        Ok(self.embedding_status())
+    }
+    
+    /// Re-embeds up to `batch` chunks still using another model and saves,
+    /// so an interrupted migration resumes where it stopped. Returns how many
+    /// chunks were migrated; 0 means the migration is complete.
+    pub async fn reembed_batch(&mut self, batch: usize) -> Result<usize, AssistantError> {
+        if self.manifest.embedding_model != self.embedding_service.model() {
+            return Err(AssistantError::EmbeddingMismatch(
+                "re-embedding has not been started for the configured model".to_string(),
+            ));
+        }
+        
+        let pending: Vec<usize> = (0..self.documents.len())
+            .filter(|&i| !self.is_current(i))
+            .take(batch)
+            .collect();
+        for &i in &pending {
+            let embedding = self.embed_primary(&self.documents[i].content.clone()).await?;
+            self.set_vector(i, embedding)?;
+        }
+        
+        if !pending.is_empty() {
+            self.save()?;
+            self.save_manifest()?;
+        } else if !self.manifest.reembed_pending.is_empty() {
+            // Only chunks deleted mid-migration can still be listed
+            self.manifest.reembed_pending.clear();
+            self.save_manifest()?;
+        }
+        Ok(pending.len())
+    }
+    
+    pub async fn add_document(&mut self, doc: Document) -> Result<(), AssistantError> {
+        if self.insert(doc).await? {
+            self.save()?;
         }
         Ok(())
     }
     
-    pub async fn search(&self, query: &str, k: usize) -> Result<Vec<Document>, AssistantError> {
-        let query_embedding = self.embedding_service.embed_text(query).await?;
+    pub async fn add_documents(&mut self, docs: Vec<Document>) -> Result<usize, AssistantError> {
+        let embeddings = self.embed_pending(&docs).await?;
+        let added = self.add_embedded(docs, embeddings)?;
+        if added > 0 {
+            self.save()?;
+        }
+        Ok(added)
+    }
+    
+    /// Indices of the chunks in `docs` that need embedding before they can be
+    /// stored: those not already known for their source, each content once.
+    pub fn pending_chunks(&self, docs: &[Document]) -> Vec<usize> {
+        let mut seen = HashSet::new();
+        (0..docs.len())
+            .filter(|&i| {
+                let meta = &docs[i].metadata;
+                !self.contains(&meta.source, &meta.content_hash)
+                    && seen.insert((meta.source.as_str(), meta.content_hash.as_str()))
+            })
+            .collect()
+    }
+    
+    /// Adds `docs` using vectors already computed for the chunks named by
+    /// [`VectorStore::pending_chunks`], without saving. Returns how many were added.
+    pub fn add_embedded(
+        &mut self,
+        docs: Vec<Document>,
+        mut embeddings: HashMap<usize, Embedding>,
+    ) -> Result<usize, AssistantError> {
+        self.check_dimensions(&embeddings)?;
+        let mut added = 0;
+        for (i, doc) in docs.into_iter().enumerate() {
+            if let Some(embedding) = embeddings.remove(&i) {
+                if self.insert_embedded(doc, embedding)? {
+                    added += 1;
+                }
+            }
+        }
+        Ok(added)
+    }
+    
+    /// Writes batched changes made by [`VectorStore::add_embedded`] and
+    /// [`VectorStore::apply_source`] to disk.
+    pub fn flush(&mut self) -> Result<(), AssistantError> {
+        self.save()
+    }
+
+    /// Replaces the chunks stored for `source` with `docs`.
+    ///
+    /// When `file_hash` matches what is already stored the call is a no-op.
+    /// Otherwise chunks whose content is unchanged are kept (and their
+    /// embeddings reused), stale ones are dropped and only new ones are embedded.
+    pub async fn sync_source(
+        &mut self,
+        source: &str,
+        file_hash: &str,
+        docs: Vec<Document>,
+    ) -> Result<SyncReport, AssistantError> {
+        if self.source_hash(source) == Some(file_hash) {
+            return self.apply_source(source, file_hash, docs, HashMap::new());
+        }
+        let embeddings = self.embed_pending(&docs).await?;
+        let report = self.apply_source(source, file_hash, docs, embeddings)?;
+        self.save()?;
+        Ok(report)
+    }
+    
+    /// [`VectorStore::sync_source`] with vectors already computed for the
+    /// chunks named by [`VectorStore::pending_chunks`], without saving. The
+    /// vectors are checked before anything changes, so a failed call leaves
+    /// the source as it was.
+    pub fn apply_source(
+        &mut self,
+        source: &str,
+        file_hash: &str,
+        docs: Vec<Document>,
+        mut embeddings: HashMap<usize, Embedding>,
+    ) -> Result<SyncReport, AssistantError> {
+        let mut report = SyncReport::default();
         
-        let mut scored_docs: Vec<(f32, Document)> = self
+        if self.source_hash(source) == Some(file_hash) {
+            report.unchanged = self.documents.iter().filter(|d| d.metadata.source == source).count();
+            return Ok(report);
+        }
+        self.check_dimensions(&embeddings)?;
+        
+        // New chunks inherit any tags and annotations given to the source
+        let inherited = self
             .documents
             .iter()
-            .filter_map(|doc| {
-                doc.embedding.as_ref().map(|emb| {
-                    let score = cosine_similarity(&query_embedding, emb);
-                    (score, doc.clone())
+            .find(|d| d.metadata.source == source)
+            .map(|d| (d.metadata.tags.clone(), d.metadata.fields.clone()));
+        
+        let wanted: HashMap<&str, &Document> = docs.iter().map(|d| (d.metadata.content_hash.as_str(), d)).collect();
+        report.removed = self.remove_where(|d| {
+            d.metadata.source == source && !wanted.contains_key(d.metadata.content_hash.as_str())
+        });
+        
+        // Kept chunks may have moved within the file
+        for doc in self.documents.iter_mut().filter(|d| d.metadata.source == source) {
+            if let Some(incoming) = wanted.get(doc.metadata.content_hash.as_str()) {
+                let moved = &incoming.metadata;
+                doc.metadata.parent_id = moved.parent_id.clone();
+                doc.metadata.chunk_index = moved.chunk_index;
+                doc.metadata.page = moved.page;
+                doc.metadata.section = moved.section.clone();
+                doc.metadata.line_start = moved.line_start;
+                doc.metadata.line_end = moved.line_end;
+            }
+            doc.metadata.file_hash = Some(file_hash.to_string());
+            report.unchanged += 1;
+        }
+        
+        for (i, mut doc) in docs.into_iter().enumerate() {
+            let Some(embedding) = embeddings.remove(&i) else {
+                continue;
+            };
+            if let Some((tags, fields)) = &inherited {
+                doc.metadata.tags = tags.clone();
+                doc.metadata.fields = fields.clone();
+            }
+            if self.insert_embedded(doc.with_file_hash(file_hash.to_string()), embedding)? {
+                report.added += 1;
+            }
+        }
+        
+        Ok(report)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Document> {
+        self.documents.iter().find(|d| d.id == id)
+    }
+    
+    /// Chunks learned from `source`, in the order they were stored.
+    pub fn documents_for_source(&self, source: &str) -> Vec<&Document> {
+        self.documents.iter().filter(|d| d.metadata.source == source).collect()
+    }
+    
+    /// Removes the chunk with `id`, returning whether it existed.
+    pub fn delete(&mut self, id: &str) -> Result<bool, AssistantError> {
+        if self.remove_where(|d| d.id == id) == 0 {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+    
+    /// Removes every chunk learned from `source`, returning how many were dropped.
+    pub fn remove_source(&mut self, source: &str) -> Result<usize, AssistantError> {
+        self.remove_sources(&[source.to_string()])
+    }
+    
+    /// Replaces the content of chunk `id` in place and re-embeds it. The id,
+    /// source and annotations are kept.
+    pub async fn update(&mut self, id: &str, content: String) -> Result<bool, AssistantError> {
+        let Some(index) = self.documents.iter().position(|d| d.id == id) else {
+            return Ok(false);
+        };
+        
+        let embedding = self.embed(&content).await?;
+        self.set_vector(index, embedding)?;
+        let doc = &mut self.documents[index];
+        doc.metadata.content_hash = content_hash(&content);
+        doc.metadata.language = detect_language(&content);
+        doc.metadata.timestamp = chrono::Utc::now();
+        doc.content = content;
+        
+        self.save()?;
+        Ok(true)
+    }
+    
+    /// Removes every chunk learned from any of `sources`, returning how many were dropped.
+    pub fn remove_sources(&mut self, sources: &[String]) -> Result<usize, AssistantError> {
+        let removed = self.remove_where(|d| sources.contains(&d.metadata.source));
+        if removed > 0 {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Distinct sources in the store, in insertion order.
+    pub fn sources(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        self.documents
+            .iter()
+            .filter(|d| seen.insert(d.metadata.source.as_str()))
+            .map(|d| d.metadata.source.clone())
+            .collect()
+    }
+
+    /// Every source with its chunk count and learning dates, by source.
+    pub fn source_summaries(&self) -> Vec<SourceSummary> {
+        let mut summaries: HashMap<&str, SourceSummary> = HashMap::new();
+        for doc in &self.documents {
+            let learned = doc.metadata.timestamp;
+            summaries
+                .entry(doc.metadata.source.as_str())
+                .and_modify(|s| {
+                    s.chunks += 1;
+                    s.first_learned = s.first_learned.min(learned);
+                    s.last_learned = s.last_learned.max(learned);
                 })
-            })
-            .collect();
+                .or_insert_with(|| SourceSummary {
+                    source: doc.metadata.source.clone(),
+                    chunks: 1,
+                    first_learned: learned,
+                    last_learned: learned,
+                });
+        }
+        let mut summaries: Vec<SourceSummary> = summaries.into_values().collect();
+        summaries.sort_by(|a, b| a.source.cmp(&b.source));
+        summaries
+    }
+    
+    pub fn stats(&self) -> StoreStats {
+        let bytes_on_disk = [
+            self.storage_path.clone(),
+            StoreManifest::path_for(&self.storage_path),
+            full_vectors_path(&self.storage_path),
+        ]
+        .iter()
+        .map(|path| disk_size(path))
+        .sum();
+        StoreStats {
+            chunks: self.documents.len(),
+            sources: self.sources().len(),
+            embedding_model: self.manifest.embedding_model.clone(),
+            dimension: self.manifest.dimension,
+            quantization: self.quantization,
+            bytes_on_disk,
+            oldest: self.documents.iter().map(|d| d.metadata.timestamp).min(),
+            newest: self.documents.iter().map(|d| d.metadata.timestamp).max(),
+        }
+    }
+    
+    /// File hash recorded for `source`, if it has been ingested before.
+    pub fn source_hash(&self, source: &str) -> Option<&str> {
+        self.documents
+            .iter()
+            .find(|d| d.metadata.source == source)
+            .and_then(|d| d.metadata.file_hash.as_deref())
+    }
+    
+    /// Adds `tags` and `fields` to every chunk whose source starts with
+    /// `source_prefix`, returning how many chunks were updated.
+    pub fn annotate(
+        &mut self,
+        source_prefix: &str,
+        tags: &[String],
+        fields: &[(String, String)],
+    ) -> Result<usize, AssistantError> {
+        let mut updated = 0;
+        for doc in self.documents.iter_mut().filter(|d| d.metadata.source.starts_with(source_prefix)) {
+            for tag in tags {
+                if !doc.metadata.tags.contains(tag) {
+                    doc.metadata.tags.push(tag.clone());
+                }
+            }
+            for (key, value) in fields {
+                doc.metadata.fields.insert(key.clone(), value.clone());
+            }
+            updated += 1;
+        }
+        if updated > 0 {
+            self.save()?;
+        }
+        Ok(updated)
+    }
+    
+    /// Returns up to `options.k` documents matching `options.filter`, dropping
+    /// any that score below `options.min_score`. Hits are ordered by similarity,
+    /// or diversified with MMR over the top `options.fetch_k` when
+    /// `options.mmr_lambda` is below 1.0.
+    pub async fn search(&self, query: &str, options: &SearchOptions) -> Result<Vec<ScoredDocument>, AssistantError> {
+        let query_embedding = self.embedding_service.embed(query).await?;
+        Ok(self.search_by_embedding(&query_embedding, options))
+    }
+    
+    /// [`VectorStore::search`] with an already embedded query. Only chunks
+    /// embedded by the same model as the query are considered.
+    pub fn search_by_embedding(&self, query: &Embedding, options: &SearchOptions) -> Vec<ScoredDocument> {
+        let mut query_embedding = query.vector.clone();
+        normalize(&mut query_embedding);
         
-        scored_docs.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+        let diversify = options.mmr_lambda < 1.0;
+        let pool = if diversify { options.fetch_k.max(options.k) } else { options.k };
+        let quantized = self.quantization != Quantization::None;
+        let rescored = quantized || self.decay.is_some();
+        let shortlist = if rescored { pool * RESCORE_FACTOR } else { pool };
+        let mut candidates: Vec<ScoredDocument> = self
+            .index
+            .top_k(&self.documents, &query.model, &query_embedding, &options.filter, shortlist)
+            .into_iter()
+            .map(|(score, row)| self.rescore(row, score, &query_embedding))
+            .collect();
+        // The cutoff is on similarity; decay only reorders what passes it
+        candidates.retain(|c| c.score >= options.min_score);
+        if let Some(decay) = &self.decay {
+            let now = Utc::now();
+            for candidate in &mut candidates {
+                candidate.score = decay.apply(candidate.score, candidate.document.metadata.timestamp, now);
+            }
+        }
+        if rescored {
+            candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        }
+        candidates.truncate(pool);
         
-        Ok(scored_docs.into_iter().take(k).map(|(_, doc)| doc).collect())
+        if diversify {
+            mmr_select(candidates, options.k, options.mmr_lambda)
+        } else {
+            candidates
+        }
     }
     
     pub fn count(&self) -> usize {
@@ -60,11 +576,333 @@ impl VectorStore {
     }
     
     pub fn export(&self) -> Result<Vec<Document>, AssistantError> {
-        Ok(self.documents.clone())
+        Ok((0..self.documents.len()).map(|row| self.stored_document(row).into_owned()).collect())
     }
     
-    fn save(&self) -> Result<(), AssistantError> {
-        let json = serde_json::to_string_pretty(&self.documents)
+    pub fn documents(&self) -> impl Iterator<Item = &Document> {
+        self.documents.iter()
+    }
+    
+    /// Merges `docs` into the store. A chunk whose content hash is already
+    /// present is skipped or replaces the existing one, per `policy`. Vectors
+    /// are kept only when they came from the configured model; anything else
+    /// is re-embedded. When a document fails to read or embed, what was
+    /// imported before it is saved and the error returned.
+    pub async fn import_documents(
+        &mut self,
+        docs: impl Iterator<Item = Result<Document, AssistantError>>,
+        policy: ConflictPolicy,
+    ) -> Result<ImportReport, AssistantError> {
+        let mut report = ImportReport::default();
+        let mut result = Ok(());
+        for doc in docs {
+            result = match doc {
+                Ok(doc) => self.import_document(doc, policy, &mut report).await,
+                Err(e) => Err(e),
+            };
+            if result.is_err() {
+                break;
+            }
+        }
+        
+        if report.imported + report.replaced > 0 {
+            let saved = self.save();
+            result = result.and(saved);
+        }
+        result.map(|_| report)
+    }
+    
+    /// Imports one document for [`VectorStore::import_documents`]. Nothing is
+    /// changed until its vector is known to fit the store.
+    async fn import_document(
+        &mut self,
+        mut doc: Document,
+        policy: ConflictPolicy,
+        report: &mut ImportReport,
+    ) -> Result<(), AssistantError> {
+        if doc.metadata.content_hash.is_empty() {
+            doc.metadata.content_hash = content_hash(&doc.content);
+        }
+        
+        let existing = self
+            .documents
+            .iter()
+            .position(|d| d.metadata.content_hash == doc.metadata.content_hash);
+        if existing.is_some() && policy == ConflictPolicy::Skip {
+            report.skipped += 1;
+            return Ok(());
+        }
+        
+        let reusable = doc.embedding_model.as_deref() == Some(self.embedding_service.model())
+            && doc.embedding.as_ref().is_some_and(|e| {
+                self.manifest.dimension.is_none_or(|dimension| e.len() == dimension)
+            });
+        let embedding = match doc.embedding.take() {
+            Some(vector) if reusable => Embedding {
+                vector,
+                model: self.embedding_service.model().to_string(),
+            },
+            _ => {
+                report.reembedded += 1;
+                self.embed(&doc.content).await?
+            }
+        };
+        self.check_dimension(&embedding)?;
+        doc.quantized = None;
+        
+        let index = match existing {
+            Some(index) => {
+                // The replaced chunk keeps its id, and with it its full vector entry
+                doc.id = self.documents[index].id.clone();
+                self.documents[index] = doc;
+                report.replaced += 1;
+                index
+            }
+            None => {
+                if self.get(&doc.id).is_some() {
+                    doc.id = uuid::Uuid::new_v4().to_string();
+                }
+                report.imported += 1;
+                self.push_document(doc)
+            }
+        };
+        self.set_vector(index, embedding)
+    }
+
+    /// Embeds and stores `doc` unless a chunk with the same source and content
+    /// hash is already present. Returns whether anything was inserted.
+    async fn insert(&mut self, doc: Document) -> Result<bool, AssistantError> {
+        if self.contains(&doc.metadata.source, &doc.metadata.content_hash) {
+            return Ok(false);
+        }
+        
+        let embedding = self.embed(&doc.content).await?;
+        self.insert_embedded(doc, embedding)
+    }
+    
+    /// Stores `doc` with a vector computed elsewhere, unless its content is
+    /// already known for its source.
+    fn insert_embedded(&mut self, mut doc: Document, embedding: Embedding) -> Result<bool, AssistantError> {
+        if self.contains(&doc.metadata.source, &doc.metadata.content_hash) {
+            return Ok(false);
+        }
+        
+        self.check_dimension(&embedding)?;
+        doc.embedding = None;
+        doc.quantized = None;
+        let row = self.push_document(doc);
+        self.set_vector(row, embedding)?;
+        Ok(true)
+    }
+    
+    /// Appends `doc` to the documents and the index, returning its position.
+    fn push_document(&mut self, mut doc: Document) -> usize {
+        self.index.push(&mut doc);
+        self.documents.push(doc);
+        self.documents.len() - 1
+    }
+    
+    /// Drops the chunks `remove` picks from the documents and the index
+    /// alike, returning how many there were.
+    fn remove_where(&mut self, remove: impl Fn(&Document) -> bool) -> usize {
+        let rows: Vec<usize> = (0..self.documents.len()).filter(|&row| remove(&self.documents[row])).collect();
+        self.index.remove(&rows);
+        self.documents.retain(|d| !remove(d));
+        rows.len()
+    }
+    
+    /// Stores `embedding` as the vector of the chunk at `index`, scaled to
+    /// unit length and quantized if the store is, and records which model
+    /// produced it.
+    fn set_vector(&mut self, index: usize, embedding: Embedding) -> Result<(), AssistantError> {
+        let Embedding { vector: mut embedding, model } = embedding;
+        normalize(&mut embedding);
+        let quantized = QuantizedVector::encode(&embedding, self.quantization);
+        if quantized.is_some() && self.keep_full {
+            if let Some(db) = &self.full_vectors {
+                db.insert(self.documents[index].id.as_bytes(), vector_to_bytes(&embedding))
+                    .map_err(sled_error)?;
+            }
+        }
+        
+        let doc = &mut self.documents[index];
+        self.manifest.reembed_pending.remove(&doc.id);
+        doc.embedding_model = Some(model);
+        doc.embedding = if quantized.is_some() { None } else { Some(embedding) };
+        doc.quantized = quantized;
+        self.index.set(index, doc);
+        Ok(())
+    }
+    
+    /// Brings the vector of the chunk at `index` into the configured form.
+    /// When the full vector is gone the chunk is left for re-embedding.
+    /// Returns whether anything changed.
+    fn convert_vector(&mut self, index: usize) -> Result<bool, AssistantError> {
+        let doc = &self.documents[index];
+        let dense = self.index.vector(index);
+        let in_form = match (dense, &doc.quantized, self.quantization) {
+            (None, None, _) => return Ok(false),
+            (Some(_), _, Quantization::None) => true,
+            (None, Some(QuantizedVector::Int8 { .. }), Quantization::Int8) => true,
+            (None, Some(QuantizedVector::Binary { .. }), Quantization::Binary) => true,
+            _ => false,
+        };
+        if in_form {
+            return Ok(false);
+        }
+        
+        let full = dense.map(<[f32]>::to_vec).or_else(|| self.full_vector(&doc.id));
+        match full {
+            Some(vector) => {
+                let model = doc.embedding_model.clone();
+                self.set_vector(index, Embedding { vector, model: String::new() })?;
+                self.documents[index].embedding_model = model;
+            }
+            None => {
+                let doc = &mut self.documents[index];
+                doc.embedding = None;
+                doc.quantized = None;
+                doc.embedding_model = None;
+                self.index.set(index, doc);
+            }
+        }
+        Ok(true)
+    }
+    
+    /// Turns a first-pass hit into a search result, rescoring it against its
+    /// full-precision vector when the in-memory one is quantized. The result
+    /// always carries a vector so MMR can compare hits.
+    fn rescore(&self, row: usize, score: f32, query: &[f32]) -> ScoredDocument {
+        let doc = &self.documents[row];
+        if let Some(vector) = self.index.vector(row) {
+            return ScoredDocument {
+                document: Document { embedding: Some(vector.to_vec()), ..doc.clone() },
+                score,
+            };
+        }
+        let (embedding, score) = match self.full_vector(&doc.id) {
+            Some(full) => {
+                let exact = cosine_similarity(query, &full);
+                (Some(full), exact)
+            }
+            None => (doc.quantized.as_ref().map(|q| q.dequantize()), score),
+        };
+        ScoredDocument {
+            document: Document { embedding, ..doc.clone() },
+            score,
+        }
+    }
+    
+    fn full_vector(&self, id: &str) -> Option<Vec<f32>> {
+        let db = self.full_vectors.as_ref()?;
+        db.get(id.as_bytes()).ok().flatten().map(|bytes| vector_from_bytes(&bytes))
+    }
+    
+    /// Drops on-disk full vectors whose chunks are no longer in the store.
+    fn prune_full_vectors(&self) -> Result<(), AssistantError> {
+        let Some(db) = &self.full_vectors else {
+            return Ok(());
+        };
+        let live: HashSet<&[u8]> = self.documents.iter().map(|d| d.id.as_bytes()).collect();
+        for key in db.iter().keys() {
+            let key = key.map_err(sled_error)?;
+            if !live.contains(key.as_ref()) {
+                db.remove(key).map_err(sled_error)?;
+            }
+        }
+        db.flush().map_err(sled_error)?;
+        Ok(())
+    }
+    
+    /// Embeds the chunks of `docs` named by [`VectorStore::pending_chunks`]
+    /// one at a time.
+    async fn embed_pending(&mut self, docs: &[Document]) -> Result<HashMap<usize, Embedding>, AssistantError> {
+        let mut embeddings = HashMap::new();
+        for i in self.pending_chunks(docs) {
+            embeddings.insert(i, self.embedding_service.embed(&docs[i].content).await?);
+        }
+        Ok(embeddings)
+    }
+    
+    /// Embeds `text`, possibly with the fallback embedder, and checks the
+    /// vector against the store's dimension.
+    async fn embed(&mut self, text: &str) -> Result<Embedding, AssistantError> {
+        let embedding = self.embedding_service.embed(text).await?;
+        self.check_dimension(&embedding)?;
+        Ok(embedding)
+    }
+    
+    /// Embeds `text` with the configured model only, as migrations must.
+    async fn embed_primary(&mut self, text: &str) -> Result<Embedding, AssistantError> {
+        let embedding = Embedding {
+            vector: self.embedding_service.embed_text(text).await?,
+            model: self.embedding_service.model().to_string(),
+        };
+        self.check_dimension(&embedding)?;
+        Ok(embedding)
+    }
+    
+    /// Records the store model's vector length the first time and rejects
+    /// vectors from it that don't match. Other models' vectors aren't checked.
+    fn check_dimension(&mut self, embedding: &Embedding) -> Result<(), AssistantError> {
+        if embedding.model != self.manifest.embedding_model
+            || self.manifest.embedding_model != self.embedding_service.model()
+        {
+            return Ok(());
+        }
+        
+        match self.manifest.dimension {
+            None => {
+                self.manifest.dimension = Some(embedding.vector.len());
+                self.save_manifest()?;
+            }
+            Some(dimension) if dimension != embedding.vector.len() => {
+                return Err(AssistantError::EmbeddingMismatch(format!(
+                    "{} now returns {}-dimensional vectors but the store holds {}-dimensional ones; run 'reembed'",
+                    self.manifest.embedding_model,
+                    embedding.vector.len(),
+                    dimension
+                )));
+            }
+            Some(_) => {}
+        }
+        Ok(())
+    }
+    
+    /// [`VectorStore::check_dimension`] for every vector of a batch.
+    fn check_dimensions(&mut self, embeddings: &HashMap<usize, Embedding>) -> Result<(), AssistantError> {
+        for embedding in embeddings.values() {
+            self.check_dimension(embedding)?;
+        }
+        Ok(())
+    }
+    
+    fn is_current(&self, row: usize) -> bool {
+        let doc = &self.documents[row];
+        self.index.dim(row).is_some()
+            && doc.embedding_model.as_deref() == Some(self.embedding_service.model())
+            && !self.manifest.reembed_pending.contains(&doc.id)
+    }
+
+    fn contains(&self, source: &str, hash: &str) -> bool {
+        self.documents
+            .iter()
+            .any(|d| d.metadata.source == source && d.metadata.content_hash == hash)
+    }
+    
+    /// The chunk at `row` as written to disk, with the full-precision vector
+    /// the index holds for it put back.
+    fn stored_document(&self, row: usize) -> Cow<'_, Document> {
+        let doc = &self.documents[row];
+        match self.index.vector(row) {
+            Some(vector) => Cow::Owned(Document { embedding: Some(vector.to_vec()), ..doc.clone() }),
+            None => Cow::Borrowed(doc),
+        }
+    }
+    
+    /// Writes the documents to disk; the index is kept up to date as they change.
+    fn save(&mut self) -> Result<(), AssistantError> {
+        let json = serde_json::to_string_pretty(&StoredDocuments(self))
             .map_err(|e| AssistantError::SerializationError(e.to_string()))?;
         
         std::fs::write(&self.storage_path, json)?;
@@ -80,7 +918,84 @@ impl VectorStore {
         self.documents = serde_json::from_str(&json)
             .map_err(|e| AssistantError::SerializationError(e.to_string()))?;
         
+        // Vectors are compared by dot product, so older stores' vectors are scaled to unit length
+        for embedding in self.documents.iter_mut().filter_map(|d| d.embedding.as_mut()) {
+            normalize(embedding);
+        }
+        
+        // Stores written before content hashing have no fingerprints or language yet
+        for doc in self.documents.iter_mut().filter(|d| d.metadata.content_hash.is_empty()) {
+            doc.metadata.content_hash = content_hash(&doc.content);
+            doc.metadata.language = detect_language(&doc.content);
+        }
+        
         Ok(())
     }
+    
+    fn load_manifest(&mut self) -> Result<(), AssistantError> {
+        let path = StoreManifest::path_for(&self.storage_path);
+        match StoreManifest::load(&path)? {
+            Some(manifest) => self.manifest = manifest,
+            None => {
+                // Stores created before manifests are assumed to use the configured model
+                self.manifest.dimension = self
+                    .documents
+                    .iter()
+                    .find_map(vector_dim);
+                self.save_manifest()?;
+            }
+        }
+        
+        let store_model = self.manifest.embedding_model.clone();
+        for doc in self.documents.iter_mut().filter(|d| d.embedding_model.is_none()) {
+            doc.embedding_model = Some(store_model.clone());
+        }
+        Ok(())
+    }
+    
+    fn save_manifest(&self) -> Result<(), AssistantError> {
+        self.manifest.save(&StoreManifest::path_for(&self.storage_path))
+    }
+}
+
+/// The documents in their on-disk form, serialized one at a time so the
+/// vectors aren't all copied out of the index at once.
+struct StoredDocuments<'a>(&'a VectorStore);
+
+impl Serialize for StoredDocuments<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let store = self.0;
+        serializer.collect_seq((0..store.documents.len()).map(|row| store.stored_document(row)))
+    }
+}
+
+/// Length of `doc`'s vector, whichever form it is held in, before the
+/// index takes the full-precision ones.
+fn vector_dim(doc: &Document) -> Option<usize> {
+    match (&doc.embedding, &doc.quantized) {
+        (Some(embedding), _) => Some(embedding.len()),
+        (None, Some(quantized)) => Some(quantized.dim()),
+        (None, None) => None,
+    }
+}
+
+/// Size of a file, or of everything under a directory; 0 if missing.
+fn disk_size(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| disk_size(&entry.path())).sum())
+        .unwrap_or(0)
 }
 
+fn full_vectors_path(storage_path: &Path) -> PathBuf {
+    storage_path.with_extension("vectors")
+}
+
+fn sled_error(e: sled::Error) -> AssistantError {
+    AssistantError::KnowledgeError(format!("full-precision vector index: {}", e))
+}
@@ -0,0 +1,178 @@
+use super::collections::DEFAULT_COLLECTION;
+use crate::{AssistantError, Result};
+use chrono::{DateTime, Utc};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant};
+
+const REGISTRY_FILE: &str = "watched_folders.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedFolder {
+    pub path: PathBuf,
+    pub added_at: DateTime<Utc>,
+    /// Collection the folder's files are learned into.
+    #[serde(default = "default_collection")]
+    pub collection: String,
+    #[serde(default)]
+    pub last_sync: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub files_synced: usize,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+/// Folders kept in sync with the knowledge base, persisted in the data dir.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatchRegistry {
+    pub folders: Vec<WatchedFolder>,
+}
+
+impl WatchRegistry {
+    pub fn load_or_default(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join(REGISTRY_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| AssistantError::SerializationError(e.to_string()))
+    }
+
+    pub fn save(&self, data_dir: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| AssistantError::SerializationError(e.to_string()))?;
+        std::fs::write(data_dir.join(REGISTRY_FILE), json)?;
+        Ok(())
+    }
+
+    /// Registers `path` to be learned into `collection`, returning false if
+    /// it was already watched.
+    pub fn add(&mut self, path: PathBuf, collection: &str) -> bool {
+        if self.folders.iter().any(|f| f.path == path) {
+            return false;
+        }
+        self.folders.push(WatchedFolder {
+            path,
+            added_at: Utc::now(),
+            collection: collection.to_string(),
+            last_sync: None,
+            files_synced: 0,
+            last_error: None,
+        });
+        true
+    }
+
+    pub fn remove(&mut self, path: &Path) -> bool {
+        let before = self.folders.len();
+        self.folders.retain(|f| f.path != path);
+        self.folders.len() != before
+    }
+
+    /// The watched folder that contains `path`, if any.
+    pub fn root_of(&self, path: &Path) -> Option<PathBuf> {
+        self.folders
+            .iter()
+            .filter(|f| path.starts_with(&f.path))
+            .max_by_key(|f| f.path.as_os_str().len())
+            .map(|f| f.path.clone())
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&WatchedFolder> {
+        self.folders.iter().find(|f| f.path == path)
+    }
+
+    pub fn get_mut(&mut self, path: &Path) -> Option<&mut WatchedFolder> {
+        self.folders.iter_mut().find(|f| f.path == path)
+    }
+}
+
+fn default_collection() -> String {
+    DEFAULT_COLLECTION.to_string()
+}
+
+/// Snapshot of the watcher for the status command.
+#[derive(Debug, Clone)]
+pub struct WatchStatus {
+    pub folders: Vec<WatchedFolder>,
+    pub pending_changes: usize,
+    pub active: bool,
+}
+
+/// Collects filesystem events for watched folders and hands out changed paths
+/// once they have been quiet for the debounce interval.
+pub struct FolderWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<PathBuf>,
+    pending: HashMap<PathBuf, Instant>,
+    debounce: Duration,
+}
+
+impl FolderWatcher {
+    pub fn new(debounce: Duration) -> Result<Self> {
+        let (tx, events) = channel();
+        let watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+            if let Ok(event) = res {
+                if matches!(event.kind, EventKind::Access(_)) {
+                    return;
+                }
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            }
+        })
+        .map_err(|e| AssistantError::KnowledgeError(e.to_string()))?;
+
+        Ok(Self {
+            watcher,
+            events,
+            pending: HashMap::new(),
+            debounce,
+        })
+    }
+
+    pub fn watch(&mut self, path: &Path) -> Result<()> {
+        self.watcher
+            .watch(path, RecursiveMode::Recursive)
+            .map_err(|e| AssistantError::KnowledgeError(e.to_string()))
+    }
+
+    pub fn unwatch(&mut self, path: &Path) -> Result<()> {
+        self.watcher
+            .unwatch(path)
+            .map_err(|e| AssistantError::KnowledgeError(e.to_string()))
+    }
+
+    /// Number of changed paths still waiting out the debounce interval.
+    pub fn pending_count(&mut self) -> usize {
+        self.drain();
+        self.pending.len()
+    }
+
+    /// Paths that have seen no new events for at least the debounce interval.
+    pub fn take_ready(&mut self) -> Vec<PathBuf> {
+        self.drain();
+
+        let now = Instant::now();
+        let ready: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, seen)| now.duration_since(**seen) >= self.debounce)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in &ready {
+            self.pending.remove(path);
+        }
+        ready
+    }
+
+    fn drain(&mut self) {
+        while let Ok(path) = self.events.try_recv() {
+            self.pending.insert(path, Instant::now());
+        }
+    }
+}
@@ -28,6 +28,9 @@ pub enum AssistantError {
     
     #[error("Model not found: {0}")]
     ModelNotFound(String),
+    
+    #[error("Embedding mismatch: {0}")]
+    EmbeddingMismatch(String),
 }
 
 pub type Result<T> = std::result::Result<T, AssistantError>;
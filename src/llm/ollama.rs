@@ -1,6 +1,7 @@
 use super::types::*;
 use crate::AssistantError;
 
+#[derive(Clone)]
 pub struct OllamaClient {
     base_url: String,
     client: reqwest::Client,
@@ -51,6 +52,45 @@ impl OllamaClient {
         Ok(chat_response.message.content)
     }
     
+    pub async fn generate(
+        &self,
+        model: &str,
+        prompt: &str,
+        raw: bool,
+        temperature: f32,
+    ) -> Result<String, AssistantError> {
+        let request = GenerateRequest {
+            model: model.to_string(),
+            prompt: prompt.to_string(),
+            stream: false,
+            raw,
+            options: Some(ChatOptions { temperature }),
+        };
+        
+        let response = self
+            .client
+            .post(format!("{}/api/generate", self.base_url))
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| AssistantError::OllamaError(e.to_string()))?;
+        
+        if !response.status().is_success() {
+            return Err(AssistantError::OllamaError(format!(
+                "HTTP {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            )));
+        }
+        
+        let generate_response: GenerateResponse = response
+            .json()
+            .await
+            .map_err(|e| AssistantError::OllamaError(e.to_string()))?;
+        
+        Ok(generate_response.response)
+    }
+    
     pub async fn embed(
         &self,
         model: &str,
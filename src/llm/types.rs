@@ -35,3 +35,20 @@ pub struct EmbedRequest {
 pub struct EmbedResponse {
     pub embedding: Vec<f32>,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GenerateRequest {
+    pub model: String,
+    pub prompt: String,
+    pub stream: bool,
+    /// Send `prompt` to the model without applying its prompt template.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub raw: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<ChatOptions>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GenerateResponse {
+    pub response: String,
+}
@@ -75,6 +75,11 @@ impl ConversationManager {
         self.messages.iter().map(|msg| msg.to_llm_message()).collect()
     }
 
+    /// When the oldest message still in the history was sent.
+    pub fn oldest_timestamp(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.messages.front().map(|msg| msg.timestamp)
+    }
+
     pub fn clear(&mut self) {
         self.messages.clear();
     }
@@ -0,0 +1,109 @@
+use crate::knowledge::documents::Document;
+use crate::knowledge::embeddings::EmbeddingService;
+use crate::knowledge::manifest::EmbeddingStatus;
+use crate::knowledge::quantization::Quantization;
+use crate::knowledge::vectorstore::{SearchOptions, VectorStore};
+use crate::Result;
+use chrono::{DateTime, Local, Utc};
+use std::path::PathBuf;
+
+/// Source recorded on every stored exchange.
+const EPISODE_SOURCE: &str = "conversation";
+
+/// Longest excerpt of a past exchange put in the prompt, in characters.
+const EPISODE_EXCERPT_CHARS: usize = 600;
+
+/// A past exchange recalled for the current message.
+#[derive(Debug, Clone)]
+pub struct Episode {
+    pub when: DateTime<Utc>,
+    pub content: String,
+    pub score: f32,
+}
+
+/// Every completed exchange, embedded so relevant ones can be recalled long
+/// after they have dropped out of the recent history.
+pub struct EpisodicMemory {
+    store: VectorStore,
+}
+
+impl EpisodicMemory {
+    pub fn open(
+        path: PathBuf,
+        embedding_service: EmbeddingService,
+        quantization: Quantization,
+        keep_full_vectors: bool,
+    ) -> Result<Self> {
+        let store = VectorStore::new(path, embedding_service)
+            .with_quantization(quantization, keep_full_vectors)?;
+        Ok(Self { store })
+    }
+
+    /// Embeds and stores one user message and the reply to it.
+    pub async fn remember(&mut self, user_message: &str, reply: &str) -> Result<()> {
+        let content = format!("User: {}\nAssistant: {}", user_message.trim(), reply.trim());
+        self.store.add_document(Document::new(content, EPISODE_SOURCE.to_string())).await
+    }
+
+    /// Up to `k` past exchanges similar to `query` scoring at least
+    /// `min_score`, best first. With `before`, only exchanges that happened
+    /// earlier are considered, so those still in the recent history are not
+    /// repeated.
+    pub async fn recall(
+        &self,
+        query: &str,
+        k: usize,
+        min_score: f32,
+        before: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Episode>> {
+        if k == 0 || self.store.count() == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut options = SearchOptions::new(k);
+        options.min_score = min_score;
+        options.filter.before = before;
+        let hits = self.store.search(query, &options).await?;
+        Ok(hits
+            .into_iter()
+            .map(|hit| Episode {
+                when: hit.document.metadata.timestamp,
+                content: hit.document.content,
+                score: hit.score,
+            })
+            .collect())
+    }
+
+    pub fn count(&self) -> usize {
+        self.store.count()
+    }
+
+    pub fn embedding_status(&self) -> EmbeddingStatus {
+        self.store.embedding_status()
+    }
+
+    pub fn store_mut(&mut self) -> &mut VectorStore {
+        &mut self.store
+    }
+}
+
+/// Lists `episodes` oldest first, each headed by its local date, after
+/// today's date so the model can place them ("last Tuesday").
+pub fn format_episodes(episodes: &[Episode], now: DateTime<Utc>) -> String {
+    let mut episodes: Vec<&Episode> = episodes.iter().collect();
+    episodes.sort_by_key(|e| e.when);
+
+    let mut formatted = format!("Today is {}.", now.with_timezone(&Local).format("%A, %-d %B %Y"));
+    for episode in episodes {
+        let mut excerpt: String = episode.content.chars().take(EPISODE_EXCERPT_CHARS).collect();
+        if excerpt.len() < episode.content.len() {
+            excerpt.push_str("...");
+        }
+        formatted.push_str(&format!(
+            "\n\n{}:\n{}",
+            episode.when.with_timezone(&Local).format("%A, %-d %B %Y at %H:%M"),
+            excerpt
+        ));
+    }
+    formatted
+}
@@ -1,2 +1,3 @@
 pub mod conversation;
+pub mod episodic;
 pub mod storage;
@@ -9,14 +9,14 @@ pub fn save_conversation(messages: &[Message], path: impl AsRef<Path>) -> Result
         .map_err(|e| AssistantError::SerializationError(e.to_string()))?;
     
     std::fs::write(path, json)
-        .map_err(|e| AssistantError::IoError(e))?;
+        .map_err(AssistantError::IoError)?;
     
     Ok(())
 }
 
 pub fn load_conversation(path: impl AsRef<Path>) -> Result<Vec<Message>> {
     let content = std::fs::read_to_string(path)
-        .map_err(|e| AssistantError::IoError(e))?;
+        .map_err(AssistantError::IoError)?;
     
     let messages: Vec<Message> = serde_json::from_str(&content)
         .map_err(|e| AssistantError::SerializationError(e.to_string()))?;
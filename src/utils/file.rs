@@ -1,7 +1,13 @@
-use std::path::Path;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use anyhow::Result;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
 use walkdir::WalkDir;
 
+/// Per-directory ignore file honoured in addition to `.gitignore` and `.ignore`.
+pub const IGNORE_FILENAME: &str = ".assistantignore";
+
 pub fn read_file(path: &str) -> Result<String> {
     Ok(std::fs::read_to_string(path)?)
 }
@@ -30,8 +36,100 @@ pub fn read_directory(path: &str, extensions: &[&str]) -> Result<Vec<String>> {
 pub fn is_text_file(path: &Path) -> bool {
     if let Some(ext) = path.extension() {
         let ext = ext.to_str().unwrap_or("");
-        matches!(ext, "txt" | "md" | "rs" | "py" | "js" | "json" | "toml" | "yaml" | "yml")
-    } else {
-        false
+        if matches!(ext, "txt" | "md" | "rs" | "py" | "js" | "json" | "toml" | "yaml" | "yml") {
+            return true;
+        }
+    }
+    
+    match mime_guess::from_path(path).first() {
+        Some(mime) => mime.type_() == mime_guess::mime::TEXT,
+        None => looks_like_text(path),
+    }
+}
+
+/// Sniffs the first few KB of a file with no recognisable extension.
+fn looks_like_text(path: &Path) -> bool {
+    let mut buf = [0u8; 8192];
+    let n = match std::fs::File::open(path).and_then(|mut f| f.read(&mut buf)) {
+        Ok(n) => n,
+        Err(_) => return false,
+    };
+    let head = &buf[..n];
+    if head.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        // A multi-byte character may straddle the end of the buffer.
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+/// Rules applied while walking a directory for ingestion.
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    /// Globs (relative to the walk root) a file must match; empty means all.
+    pub include: Vec<String>,
+    /// Globs (relative to the walk root) that exclude a file.
+    pub exclude: Vec<String>,
+    /// Files larger than this many bytes are skipped; 0 disables the limit.
+    pub max_file_size: u64,
+}
+
+#[derive(Debug, Clone)]
+pub enum WalkEntry {
+    File(PathBuf),
+    Skipped(PathBuf, String),
+}
+
+/// Walks `root` honouring ignore files and `options`, returning candidate
+/// files along with anything that was skipped and why. Files filtered by an
+/// ignore file or by the include/exclude globs are not reported. Whether a
+/// candidate's format can actually be read is left to the caller.
+pub fn walk_files(root: &Path, options: &WalkOptions) -> Result<Vec<WalkEntry>> {
+    let include = build_globset(&options.include)?;
+    let exclude = build_globset(&options.exclude)?;
+    
+    let walker = WalkBuilder::new(root)
+        .follow_links(true)
+        .require_git(false)
+        .add_custom_ignore_filename(IGNORE_FILENAME)
+        .build();
+    
+    let mut entries = Vec::new();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                entries.push(WalkEntry::Skipped(root.to_path_buf(), e.to_string()));
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        
+        let path = entry.into_path();
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        if exclude.is_match(relative) || (!options.include.is_empty() && !include.is_match(relative)) {
+            continue;
+        }
+        
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        if options.max_file_size > 0 && size > options.max_file_size {
+            entries.push(WalkEntry::Skipped(path, format!("larger than {} bytes", options.max_file_size)));
+        } else {
+            entries.push(WalkEntry::File(path));
+        }
+    }
+    
+    Ok(entries)
+}
+
+fn build_globset(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
     }
+    Ok(builder.build()?)
 }
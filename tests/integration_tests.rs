@@ -0,0 +1,362 @@
+use assistant_agent::knowledge::cache::EmbeddingCache;
+use assistant_agent::knowledge::collections::Collections;
+use assistant_agent::knowledge::documents::Document;
+use assistant_agent::knowledge::embeddings::{Embedding, EmbeddingBackend, EmbeddingService};
+use assistant_agent::knowledge::graph::{Extraction, KnowledgeGraph};
+use assistant_agent::knowledge::hashing::HashingEmbedder;
+use assistant_agent::knowledge::interchange::{read_documents, write_documents, ConflictPolicy};
+use assistant_agent::knowledge::quantization::Quantization;
+use assistant_agent::knowledge::vectorstore::{SearchOptions, VectorStore};
+use assistant_agent::llm::ollama::OllamaClient;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// An embedding service that never talks to Ollama.
+fn local_embeddings() -> EmbeddingService {
+    EmbeddingService::new(OllamaClient::new("http://127.0.0.1:9".to_string()), "unused".to_string())
+        .with_backend(EmbeddingBackend::Local, HashingEmbedder::new(64))
+}
+
+fn store_path(dir: &Path) -> PathBuf {
+    dir.join("documents.json")
+}
+
+/// A fresh directory under `dir`, for tests that need more than one store.
+fn subdir(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    std::fs::create_dir_all(&path).unwrap();
+    path
+}
+
+fn open_store(dir: &Path) -> VectorStore {
+    VectorStore::new(store_path(dir), local_embeddings())
+}
+
+const NOTES: [(&str, &str); 4] = [
+    ("rust.md", "Rust borrow checker ownership lifetimes"),
+    ("garden.md", "tomato seedlings need sunlight and compost"),
+    ("bread.md", "sourdough starter flour water fermentation"),
+    ("bikes.md", "bicycle chain lubrication and gear shifting"),
+];
+
+async fn learn_notes(store: &mut VectorStore) {
+    for (source, text) in NOTES {
+        store.add_document(Document::new(text.to_string(), source.to_string())).await.unwrap();
+    }
+}
+
+async fn top_source(store: &VectorStore, query: &str) -> Option<String> {
+    let hits = store.search(query, &SearchOptions::new(1)).await.unwrap();
+    hits.first().map(|hit| hit.document.metadata.source.clone())
+}
+
+#[tokio::test]
+async fn search_follows_removals() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = open_store(dir.path());
+    learn_notes(&mut store).await;
+
+    assert_eq!(store.remove_source("garden.md").unwrap(), 1);
+    assert_eq!(store.count(), 3);
+    assert_eq!(top_source(&store, "sourdough flour").await.as_deref(), Some("bread.md"));
+    assert_eq!(top_source(&store, "bicycle gear").await.as_deref(), Some("bikes.md"));
+    assert_eq!(top_source(&store, "rust ownership").await.as_deref(), Some("rust.md"));
+
+    let id = store.documents_for_source("rust.md")[0].id.clone();
+    assert!(store.delete(&id).unwrap());
+    let hits = store.search("rust ownership", &SearchOptions::new(10)).await.unwrap();
+    assert_eq!(hits.len(), 2);
+    assert!(hits.iter().all(|hit| hit.document.metadata.source != "rust.md"));
+}
+
+#[tokio::test]
+async fn vectors_survive_a_reload() {
+    let dir = tempfile::tempdir().unwrap();
+    {
+        let mut store = open_store(dir.path());
+        learn_notes(&mut store).await;
+        store.remove_source("bread.md").unwrap();
+    }
+
+    let store = open_store(dir.path());
+    assert_eq!(store.count(), 3);
+    assert_eq!(top_source(&store, "tomato compost").await.as_deref(), Some("garden.md"));
+    assert_eq!(store.vector_memory().vectors, 3);
+    assert!(store.export().unwrap().iter().all(|doc| doc.embedding.as_ref().is_some_and(|e| e.len() == 64)));
+}
+
+#[tokio::test]
+async fn quantized_search_follows_removals() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = open_store(dir.path()).with_quantization(Quantization::Int8, true).unwrap();
+    learn_notes(&mut store).await;
+
+    store.remove_source("rust.md").unwrap();
+    assert_eq!(top_source(&store, "bicycle chain").await.as_deref(), Some("bikes.md"));
+    let hit = &store.search("sourdough", &SearchOptions::new(1)).await.unwrap()[0];
+    assert_eq!(hit.document.metadata.source, "bread.md");
+    assert!(hit.document.embedding.is_some());
+}
+
+#[tokio::test]
+async fn same_model_reembed_keeps_knowledge_searchable() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = open_store(dir.path());
+    learn_notes(&mut store).await;
+
+    let status = store.begin_reembed().unwrap();
+    assert!(status.is_migrating());
+    assert_eq!(status.stale, 4);
+    assert_eq!(top_source(&store, "tomato compost").await.as_deref(), Some("garden.md"));
+
+    assert_eq!(store.reembed_batch(3).await.unwrap(), 3);
+    drop(store);
+
+    let mut store = open_store(dir.path());
+    assert_eq!(store.embedding_status().stale, 1);
+    assert_eq!(top_source(&store, "sourdough flour").await.as_deref(), Some("bread.md"));
+    assert_eq!(store.reembed_batch(3).await.unwrap(), 1);
+    assert_eq!(store.reembed_batch(3).await.unwrap(), 0);
+    assert!(!store.embedding_status().is_migrating());
+}
+
+fn extraction(json: &str) -> Extraction {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn graph_finds_mentions_and_forgets_sources() {
+    let dir = tempfile::tempdir().unwrap();
+    let graph = KnowledgeGraph::open(&dir.path().join("graph")).unwrap();
+    graph
+        .add(
+            "billing.md",
+            &extraction(
+                r#"{"entities": [{"name": "Billing Service", "type": "service"}],
+                    "relations": [{"subject": "Billing Service", "predicate": "depends on", "object": "Postgres"}]}"#,
+            ),
+        )
+        .unwrap();
+    graph
+        .add(
+            "db.md",
+            &extraction(r#"{"relations": [{"subject": "Payments Team", "predicate": "runs", "object": "Postgres"}]}"#),
+        )
+        .unwrap();
+
+    let mentioned = graph.mentioned_in("Does the billing-service still use postgres?").unwrap();
+    assert_eq!(mentioned, vec!["billing service", "postgres"]);
+    assert!(graph.mentioned_in("billing on its own").unwrap().is_empty());
+
+    assert_eq!(graph.remove_source("billing.md").unwrap(), 1);
+    assert!(graph.entity("Billing Service").unwrap().is_none());
+    assert!(graph.mentioned_in("billing service").unwrap().is_empty());
+    let postgres = graph.neighbourhood("postgres").unwrap().unwrap();
+    assert_eq!(postgres.entity.sources, vec!["db.md"]);
+    assert_eq!(postgres.incoming.len(), 1);
+    assert_eq!(graph.stats().relations, 1);
+    drop(graph);
+
+    let graph = KnowledgeGraph::open(&dir.path().join("graph")).unwrap();
+    assert_eq!(graph.mentioned_in("who runs Postgres").unwrap(), vec!["postgres"]);
+}
+
+#[test]
+fn graph_indexes_a_graph_built_without_lookup_trees() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("graph");
+    let graph = KnowledgeGraph::open(&path).unwrap();
+    graph
+        .add("a.md", &extraction(r#"{"relations": [{"subject": "Ada", "predicate": "wrote", "object": "Notes"}]}"#))
+        .unwrap();
+    graph.flush().unwrap();
+    drop(graph);
+
+    let db = sled::open(&path).unwrap();
+    for tree in ["mentions", "source_entities", "source_relations"] {
+        db.drop_tree(tree).unwrap();
+    }
+    db.flush().unwrap();
+    drop(db);
+
+    let graph = KnowledgeGraph::open(&path).unwrap();
+    assert_eq!(graph.mentioned_in("what did ada write").unwrap(), vec!["ada"]);
+    assert_eq!(graph.remove_source("a.md").unwrap(), 1);
+    assert_eq!(graph.stats().entities, 0);
+}
+
+#[tokio::test]
+async fn failed_sync_leaves_the_source_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = open_store(dir.path());
+    let old = vec![Document::new("first version".to_string(), "note.md".to_string())];
+    store.sync_source("note.md", "v1", old).await.unwrap();
+
+    let new = vec![
+        Document::new("second version".to_string(), "note.md".to_string()),
+        Document::new("with another chunk".to_string(), "note.md".to_string()),
+    ];
+    let model = store.embedding_status().store_model;
+    let embeddings = HashMap::from([
+        (0, Embedding { vector: vec![1.0; 64], model: model.clone() }),
+        (1, Embedding { vector: vec![1.0; 32], model }),
+    ]);
+    assert!(store.apply_source("note.md", "v2", new, embeddings).is_err());
+
+    let kept = store.documents_for_source("note.md");
+    assert_eq!(kept.len(), 1);
+    assert_eq!(kept[0].content, "first version");
+    assert_eq!(store.source_hash("note.md"), Some("v1"));
+}
+
+fn version(chunks: &[&str]) -> Vec<Document> {
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, text)| {
+            Document::new(text.to_string(), "note.md".to_string())
+                .with_position("note".to_string(), i)
+                .with_lines(i * 10 + 1, i * 10 + 9)
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn sync_keeps_unchanged_chunks_and_replaces_the_rest() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = open_store(dir.path());
+    let report = store.sync_source("note.md", "v1", version(&["intro paragraph", "setup steps"])).await.unwrap();
+    assert_eq!((report.added, report.removed, report.unchanged), (2, 0, 0));
+    store.annotate("note.md", &["work".to_string()], &[("project".to_string(), "atlas".to_string())]).unwrap();
+    let setup_id = store.documents_for_source("note.md")[1].id.clone();
+
+    // The same file hash is a no-op
+    let report = store.sync_source("note.md", "v1", version(&["ignored"])).await.unwrap();
+    assert!(report.is_noop());
+    assert_eq!(report.unchanged, 2);
+
+    // "setup steps" moves to the top and keeps its vector; the intro is replaced
+    let new = version(&["setup steps", "usage notes"]);
+    assert_eq!(store.pending_chunks(&new), vec![1]);
+    let report = store.sync_source("note.md", "v2", new).await.unwrap();
+    assert_eq!((report.added, report.removed, report.unchanged), (1, 1, 1));
+
+    let docs = store.documents_for_source("note.md");
+    assert_eq!(docs.len(), 2);
+    let setup = docs.iter().find(|d| d.content == "setup steps").unwrap();
+    assert_eq!(setup.id, setup_id);
+    assert_eq!(setup.metadata.chunk_index, Some(0));
+    assert_eq!(setup.metadata.line_start, Some(1));
+    let usage = docs.iter().find(|d| d.content == "usage notes").unwrap();
+    assert_eq!(usage.metadata.tags, vec!["work"]);
+    assert_eq!(usage.metadata.fields.get("project").map(String::as_str), Some("atlas"));
+    assert!(docs.iter().all(|d| d.metadata.file_hash.as_deref() == Some("v2")));
+    assert_eq!(store.source_hash("note.md"), Some("v2"));
+    assert_eq!(top_source(&store, "usage notes").await.as_deref(), Some("note.md"));
+    let hits = store.search("intro paragraph", &SearchOptions::new(5)).await.unwrap();
+    assert!(hits.iter().all(|hit| hit.document.content != "intro paragraph"));
+}
+
+#[tokio::test]
+async fn export_round_trips_through_json_and_jsonl() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut source = open_store(&subdir(dir.path(), "source"));
+    learn_notes(&mut source).await;
+
+    for name in ["notes.json", "notes.jsonl"] {
+        let path = dir.path().join(name);
+        assert_eq!(write_documents(&path, source.documents_with_vectors(), true).unwrap(), 4);
+
+        let target_dir = tempfile::tempdir().unwrap();
+        let mut target = open_store(target_dir.path());
+        target.add_document(Document::new(NOTES[0].1.to_string(), "mine.md".to_string())).await.unwrap();
+        let mine = target.documents_for_source("mine.md")[0].id.clone();
+
+        let report = target.import_documents(read_documents(&path).unwrap(), ConflictPolicy::Skip).await.unwrap();
+        assert_eq!((report.imported, report.skipped, report.replaced, report.reembedded), (3, 1, 0, 0));
+        assert_eq!(target.documents_for_source("mine.md").len(), 1);
+
+        let report = target.import_documents(read_documents(&path).unwrap(), ConflictPolicy::Replace).await.unwrap();
+        assert_eq!((report.imported, report.skipped, report.replaced, report.reembedded), (0, 0, 4, 0));
+        assert_eq!(target.count(), 4);
+        // The replaced chunk keeps its id but takes the imported source
+        assert_eq!(target.get(&mine).unwrap().metadata.source, "rust.md");
+        drop(target);
+
+        let target = open_store(target_dir.path());
+        assert_eq!(target.count(), 4);
+        assert_eq!(top_source(&target, "sourdough flour").await.as_deref(), Some("bread.md"));
+    }
+}
+
+#[tokio::test]
+async fn import_without_vectors_reembeds_them() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut source = open_store(&subdir(dir.path(), "source"));
+    learn_notes(&mut source).await;
+    let path = dir.path().join("bare.jsonl");
+    write_documents(&path, source.documents_with_vectors(), false).unwrap();
+
+    let mut target = open_store(&subdir(dir.path(), "target"));
+    let report = target.import_documents(read_documents(&path).unwrap(), ConflictPolicy::Skip).await.unwrap();
+    assert_eq!((report.imported, report.reembedded), (4, 4));
+    assert_eq!(top_source(&target, "bicycle gear").await.as_deref(), Some("bikes.md"));
+}
+
+#[tokio::test]
+async fn failed_import_keeps_what_came_before() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut source = open_store(&subdir(dir.path(), "source"));
+    learn_notes(&mut source).await;
+    let path = dir.path().join("broken.jsonl");
+    write_documents(&path, source.documents_with_vectors().take(2), true).unwrap();
+    let mut contents = std::fs::read_to_string(&path).unwrap();
+    contents.push_str("{not json\n");
+    std::fs::write(&path, contents).unwrap();
+
+    let target_dir = subdir(dir.path(), "target");
+    let mut target = open_store(&target_dir);
+    assert!(target.import_documents(read_documents(&path).unwrap(), ConflictPolicy::Skip).await.is_err());
+    drop(target);
+    assert_eq!(open_store(&target_dir).count(), 2);
+}
+
+#[test]
+fn collections_ignore_a_directory_named_default() {
+    let dir = tempfile::tempdir().unwrap();
+    subdir(dir.path(), "collections/default");
+    subdir(dir.path(), "collections/work");
+    subdir(dir.path(), "collections/Not Valid");
+
+    let collections = Collections::open(dir.path().to_path_buf(), local_embeddings(), Quantization::Int8, true).unwrap();
+    assert_eq!(collections.names(), vec!["default", "work"]);
+}
+
+#[test]
+fn embedding_cache_counts_lookups_and_separates_models() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cache");
+    let cache = EmbeddingCache::open(&path).unwrap();
+
+    assert_eq!(cache.get("nomic", "hello"), None);
+    cache.insert("nomic", "hello", &[0.25, -0.5]);
+    cache.insert("nomic-embed", "hello", &[1.0, 0.0]);
+    assert_eq!(cache.get("nomic", "hello"), Some(vec![0.25, -0.5]));
+    assert_eq!(cache.get("nomic-embed", "hello"), Some(vec![1.0, 0.0]));
+    assert_eq!(cache.get("nomic", "goodbye"), None);
+
+    // Clones share the counters
+    let stats = cache.clone().stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (2, 2, 2));
+    assert!((stats.hit_rate() - 0.5).abs() < 1e-6);
+
+    // A model whose name starts another's only clears its own vectors
+    assert_eq!(cache.clear_model("nomic").unwrap(), 1);
+    assert_eq!(cache.get("nomic", "hello"), None);
+    drop(cache);
+
+    let cache = EmbeddingCache::open(&path).unwrap();
+    assert_eq!(cache.get("nomic-embed", "hello"), Some(vec![1.0, 0.0]));
+    assert_eq!(cache.stats().entries, 1);
+    assert_eq!(cache.stats().misses, 0);
+}
@@ -0,0 +1,692 @@
+use assistant_agent::agent::chain::parse_citations;
+use assistant_agent::agent::evaluation::{EvalCase, QueryResult};
+use assistant_agent::knowledge::collections::validate_name;
+use assistant_agent::knowledge::decay::TimeDecay;
+use assistant_agent::knowledge::documents::{Document, DocumentMetadata};
+use assistant_agent::knowledge::embeddings::cosine_similarity;
+use assistant_agent::knowledge::expansion::{expand_hits, ContextExpansion};
+use assistant_agent::knowledge::filter::SearchFilter;
+use assistant_agent::knowledge::fusion::reciprocal_rank_fusion;
+use assistant_agent::knowledge::graph::{Entity, Neighbourhood, Relation};
+use assistant_agent::knowledge::hashing::HashingEmbedder;
+use assistant_agent::knowledge::mmr::mmr_select;
+use assistant_agent::knowledge::quantization::{PreparedQuery, Quantization, QuantizedVector};
+use assistant_agent::knowledge::vectorstore::ScoredDocument;
+use assistant_agent::knowledge::watcher::WatchRegistry;
+use assistant_agent::memory::episodic::{format_episodes, Episode};
+use assistant_agent::utils::file::{is_text_file, walk_files, WalkEntry, WalkOptions, IGNORE_FILENAME};
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+use std::path::{Path, PathBuf};
+
+fn relation(subject: &str, predicate: &str, object: &str, sources: &[&str]) -> Relation {
+    Relation {
+        subject: subject.to_string(),
+        predicate: predicate.to_string(),
+        object: object.to_string(),
+        sources: sources.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+fn billing_neighbourhood() -> Neighbourhood {
+    Neighbourhood {
+        entity: Entity {
+            name: "Billing".to_string(),
+            kind: "service".to_string(),
+            sources: vec!["work/billing.md".to_string(), "personal/notes.md".to_string()],
+        },
+        outgoing: vec![
+            relation("Billing", "depends on", "Postgres", &["work/billing.md"]),
+            relation("Billing", "reminds me of", "Taxes", &["personal/notes.md"]),
+        ],
+        incoming: vec![relation("Payments team", "owns", "Billing", &["work/billing.md", "personal/notes.md"])],
+    }
+}
+
+#[test]
+fn neighbourhood_within_keeps_facts_from_accepted_sources() {
+    let hood = billing_neighbourhood().within(|source| source.starts_with("work/")).unwrap();
+    let outgoing: Vec<String> = hood.outgoing.iter().map(Relation::describe).collect();
+    assert_eq!(outgoing, vec!["Billing depends on Postgres"]);
+    assert_eq!(hood.incoming.len(), 1);
+}
+
+#[test]
+fn neighbourhood_within_drops_an_entity_no_accepted_source_mentions() {
+    assert!(billing_neighbourhood().within(|source| source.starts_with("archive/")).is_none());
+}
+
+fn case(relevant: &[&str]) -> EvalCase {
+    EvalCase {
+        query: "q".to_string(),
+        relevant: relevant.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+fn ranking(sources: &[&str]) -> Vec<String> {
+    sources.iter().map(|s| s.to_string()).collect()
+}
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!((actual - expected).abs() < 1e-6, "{} != {}", actual, expected);
+}
+
+#[test]
+fn eval_hit_at_first_rank_is_perfect() {
+    let result = QueryResult::score(&case(&["a.md"]), ranking(&["a.md", "b.md", "c.md"]), 3);
+    assert_close(result.recall, 1.0);
+    assert_close(result.reciprocal_rank, 1.0);
+    assert_close(result.ndcg, 1.0);
+}
+
+#[test]
+fn eval_hit_at_last_rank_is_discounted() {
+    let result = QueryResult::score(&case(&["c.md"]), ranking(&["a.md", "b.md", "c.md"]), 3);
+    assert_close(result.recall, 1.0);
+    assert_close(result.reciprocal_rank, 1.0 / 3.0);
+    assert_close(result.ndcg, 0.5);
+}
+
+#[test]
+fn eval_hit_past_k_does_not_count() {
+    let result = QueryResult::score(&case(&["d.md"]), ranking(&["a.md", "b.md", "c.md", "d.md"]), 3);
+    assert_close(result.recall, 0.0);
+    assert_close(result.reciprocal_rank, 0.0);
+    assert_close(result.ndcg, 0.0);
+    assert_eq!(result.retrieved.len(), 3);
+}
+
+#[test]
+fn eval_no_hit_scores_zero() {
+    let result = QueryResult::score(&case(&["x.md", "y.md"]), ranking(&["a.md", "b.md"]), 5);
+    assert_close(result.recall, 0.0);
+    assert_close(result.reciprocal_rank, 0.0);
+    assert_close(result.ndcg, 0.0);
+}
+
+#[test]
+fn eval_ranks_sources_by_their_best_chunk() {
+    // Repeated chunks of a.md take one rank, so b.md is second, not fourth
+    let result = QueryResult::score(&case(&["b.md"]), ranking(&["a.md", "a.md", "a.md", "b.md"]), 2);
+    assert_eq!(result.retrieved, ranking(&["a.md", "b.md"]));
+    assert_close(result.reciprocal_rank, 0.5);
+    assert_close(result.recall, 1.0);
+}
+
+#[test]
+fn eval_matches_expected_path_suffixes() {
+    let retrieved = ranking(&["/home/me/notes/rust.md", "/home/me/trust.md"]);
+    let result = QueryResult::score(&case(&["notes/rust.md"]), retrieved, 2);
+    assert_close(result.reciprocal_rank, 1.0);
+    // Suffixes match whole path components only
+    let result = QueryResult::score(&case(&["rust.md"]), ranking(&["/home/me/trust.md"]), 2);
+    assert_close(result.recall, 0.0);
+}
+
+#[test]
+fn eval_metrics_stay_within_one_for_overlapping_labels() {
+    // Both labels name the same retrieved file; it is found once, not twice
+    let labels = case(&["rust.md", "notes/rust.md", "rust.md"]);
+    let result = QueryResult::score(&labels, ranking(&["/home/me/notes/rust.md", "other.md"]), 2);
+    assert_close(result.recall, 0.5);
+    assert!(result.ndcg <= 1.0);
+    assert_close(result.reciprocal_rank, 1.0);
+}
+
+#[test]
+fn eval_ndcg_rewards_earlier_hits() {
+    let labels = case(&["a.md", "b.md"]);
+    let early = QueryResult::score(&labels, ranking(&["a.md", "b.md", "c.md"]), 3);
+    let late = QueryResult::score(&labels, ranking(&["c.md", "a.md", "b.md"]), 3);
+    assert_close(early.ndcg, 1.0);
+    assert!(late.ndcg < early.ndcg);
+    assert_close(late.recall, 1.0);
+}
+
+fn metadata(source: &str) -> DocumentMetadata {
+    let text = "Das ist ein ganz normaler deutscher Satz über das Wetter heute.";
+    let mut metadata = Document::new(text.to_string(), source.to_string()).metadata;
+    metadata.timestamp = Utc.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap();
+    metadata.tags = vec!["Work".to_string(), "billing".to_string()];
+    metadata.fields.insert("project".to_string(), "atlas".to_string());
+    metadata
+}
+
+#[test]
+fn filter_parses_every_term() {
+    let expr = "source:notes/work tag:work tag:urgent after:2024-01-01 before:2024-06-30T12:00:00Z lang:deu project=billing";
+    let filter = SearchFilter::parse(expr).unwrap();
+    assert_eq!(filter.source_prefix.as_deref(), Some("notes/work"));
+    assert_eq!(filter.tags, vec!["work", "urgent"]);
+    assert_eq!(filter.after, Some(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()));
+    assert_eq!(filter.before, Some(Utc.with_ymd_and_hms(2024, 6, 30, 12, 0, 0).unwrap()));
+    assert_eq!(filter.language.as_deref(), Some("deu"));
+    assert_eq!(filter.fields, vec![("project".to_string(), "billing".to_string())]);
+}
+
+#[test]
+fn filter_rejects_unknown_terms_and_bad_dates() {
+    assert!(SearchFilter::parse("colour:blue").is_err());
+    assert!(SearchFilter::parse("loose-word").is_err());
+    assert!(SearchFilter::parse("after:15/03/2024").is_err());
+}
+
+#[test]
+fn filter_round_trips_through_display() {
+    let expr = "source:notes tag:work after:2024-01-01 lang:eng project=billing";
+    let filter = SearchFilter::parse(expr).unwrap();
+    assert_eq!(filter.to_string(), expr);
+    assert_eq!(SearchFilter::parse(&filter.to_string()).unwrap(), filter);
+}
+
+#[test]
+fn empty_filter_matches_everything() {
+    let filter = SearchFilter::parse("   ").unwrap();
+    assert!(filter.is_empty());
+    assert!(filter.matches(&metadata("anything.md")));
+}
+
+#[test]
+fn filter_matches_source_prefix_and_tags() {
+    let meta = metadata("notes/work/billing.md");
+    assert!(SearchFilter::parse("source:notes/work").unwrap().matches(&meta));
+    assert!(!SearchFilter::parse("source:notes/home").unwrap().matches(&meta));
+    // Tags compare case-insensitively and all of them must be present
+    assert!(SearchFilter::parse("tag:work tag:BILLING").unwrap().matches(&meta));
+    assert!(!SearchFilter::parse("tag:work tag:urgent").unwrap().matches(&meta));
+}
+
+#[test]
+fn filter_date_range_is_half_open() {
+    let meta = metadata("a.md");
+    assert!(SearchFilter::parse("after:2024-03-15T12:00:00Z").unwrap().matches(&meta));
+    assert!(!SearchFilter::parse("before:2024-03-15T12:00:00Z").unwrap().matches(&meta));
+    assert!(SearchFilter::parse("after:2024-03-01 before:2024-04-01").unwrap().matches(&meta));
+    assert!(!SearchFilter::parse("after:2024-03-16").unwrap().matches(&meta));
+}
+
+#[test]
+fn filter_matches_language_by_code_or_name() {
+    let meta = metadata("a.md");
+    assert_eq!(meta.language.as_deref(), Some("deu"));
+    assert!(SearchFilter::parse("lang:deu").unwrap().matches(&meta));
+    assert!(SearchFilter::parse("lang:german").unwrap().matches(&meta));
+    assert!(!SearchFilter::parse("lang:eng").unwrap().matches(&meta));
+}
+
+#[test]
+fn filter_matches_fields_exactly() {
+    let meta = metadata("a.md");
+    assert!(SearchFilter::parse("project=atlas").unwrap().matches(&meta));
+    assert!(!SearchFilter::parse("project=atlas2").unwrap().matches(&meta));
+    assert!(!SearchFilter::parse("owner=me").unwrap().matches(&meta));
+}
+
+fn scored(source: &str, score: f32, embedding: Option<Vec<f32>>) -> ScoredDocument {
+    let mut document = Document::new(format!("chunk of {}", source), source.to_string());
+    document.embedding = embedding;
+    ScoredDocument { document, score }
+}
+
+fn sources(hits: &[ScoredDocument]) -> Vec<&str> {
+    hits.iter().map(|hit| hit.document.metadata.source.as_str()).collect()
+}
+
+fn near_duplicate_candidates() -> Vec<ScoredDocument> {
+    vec![
+        scored("a.md", 0.9, Some(vec![1.0, 0.0])),
+        scored("a-copy.md", 0.85, Some(vec![0.99, 0.01])),
+        scored("b.md", 0.7, Some(vec![0.0, 1.0])),
+    ]
+}
+
+#[test]
+fn mmr_with_lambda_one_keeps_relevance_order() {
+    let picked = mmr_select(near_duplicate_candidates(), 3, 1.0);
+    assert_eq!(sources(&picked), vec!["a.md", "a-copy.md", "b.md"]);
+}
+
+#[test]
+fn mmr_pushes_near_duplicates_down() {
+    let picked = mmr_select(near_duplicate_candidates(), 2, 0.5);
+    assert_eq!(sources(&picked), vec!["a.md", "b.md"]);
+}
+
+#[test]
+fn mmr_stops_at_k_or_when_candidates_run_out() {
+    assert_eq!(mmr_select(near_duplicate_candidates(), 1, 0.5).len(), 1);
+    assert_eq!(mmr_select(near_duplicate_candidates(), 10, 0.5).len(), 3);
+    assert!(mmr_select(Vec::new(), 3, 0.5).is_empty());
+}
+
+#[test]
+fn mmr_treats_missing_embeddings_as_dissimilar() {
+    let candidates = vec![
+        scored("a.md", 0.9, Some(vec![1.0, 0.0])),
+        scored("a-copy.md", 0.85, Some(vec![1.0, 0.0])),
+        scored("unembedded.md", 0.6, None),
+    ];
+    let picked = mmr_select(candidates, 2, 0.5);
+    assert_eq!(sources(&picked), vec!["a.md", "unembedded.md"]);
+}
+
+#[test]
+fn quantization_parses_its_aliases() {
+    assert_eq!(Quantization::parse("off"), Some(Quantization::None));
+    assert_eq!(Quantization::parse(" Scalar "), Some(Quantization::Int8));
+    assert_eq!(Quantization::parse("1bit"), Some(Quantization::Binary));
+    assert_eq!(Quantization::parse("pq"), None);
+    assert!(QuantizedVector::encode(&[1.0], Quantization::None).is_none());
+}
+
+#[test]
+fn int8_round_trip_stays_close() {
+    let vector = vec![0.5, -0.25, 0.125, -1.0, 0.0, 0.75, 0.3, -0.6, 0.9];
+    let quantized = QuantizedVector::encode(&vector, Quantization::Int8).unwrap();
+    assert_eq!(quantized.dim(), vector.len());
+    assert_eq!(quantized.size_bytes(), vector.len() + 8);
+    for (original, restored) in vector.iter().zip(quantized.dequantize()) {
+        assert!((original - restored).abs() <= 1.0 / 254.0 + 1e-6, "{} vs {}", original, restored);
+    }
+}
+
+#[test]
+fn int8_score_approximates_cosine() {
+    let a = vec![0.2, 0.4, -0.1, 0.8, 0.3, -0.5, 0.1, 0.0, 0.6];
+    let b = vec![0.1, 0.5, -0.2, 0.7, 0.2, -0.4, 0.3, 0.1, 0.5];
+    let query = PreparedQuery::new(&a);
+    let score = query.score(&QuantizedVector::encode(&b, Quantization::Int8).unwrap());
+    let exact = cosine_similarity(&a, &b);
+    assert!((score - exact).abs() < 0.01, "{} vs {}", score, exact);
+    assert_eq!(query.score(&QuantizedVector::encode(&[1.0, 2.0], Quantization::Int8).unwrap()), 0.0);
+}
+
+#[test]
+fn binary_round_trip_keeps_signs_at_unit_length() {
+    let vector = vec![0.3, -0.2, 0.0, 5.0, -1.0, 0.1, 0.2, -0.3, 0.4, -0.9];
+    let quantized = QuantizedVector::encode(&vector, Quantization::Binary).unwrap();
+    assert_eq!(quantized.dim(), 10);
+    assert_eq!(quantized.size_bytes(), 2 + std::mem::size_of::<usize>());
+    let restored = quantized.dequantize();
+    for (original, restored) in vector.iter().zip(&restored) {
+        assert_eq!(*original > 0.0, *restored > 0.0);
+    }
+    assert_close(restored.iter().map(|x| x * x).sum::<f32>(), 1.0);
+}
+
+#[test]
+fn binary_score_follows_hamming_distance() {
+    let query = [1.0, 1.0, 1.0, 1.0, -1.0, -1.0, -1.0, -1.0];
+    let prepared = PreparedQuery::new(&query);
+    let score = |vector: &[f32]| prepared.score(&QuantizedVector::encode(vector, Quantization::Binary).unwrap());
+
+    assert_close(score(&query), 1.0);
+    let opposite: Vec<f32> = query.iter().map(|x| -x).collect();
+    assert_close(score(&opposite), -1.0);
+    // Four of eight bits differ: half the way round, so orthogonal
+    assert_close(score(&[1.0, 1.0, -1.0, -1.0, 1.0, 1.0, -1.0, -1.0]), 0.0);
+    // Two differing bits are closer than four
+    assert!(score(&[1.0, 1.0, 1.0, -1.0, 1.0, -1.0, -1.0, -1.0]) > 0.5);
+}
+
+#[test]
+fn quantized_vectors_survive_serialization() {
+    for quantization in [Quantization::Int8, Quantization::Binary] {
+        let quantized = QuantizedVector::encode(&[0.5, -0.5, 0.25], quantization).unwrap();
+        let json = serde_json::to_string(&quantized).unwrap();
+        let restored: QuantizedVector = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.dequantize(), quantized.dequantize());
+    }
+}
+
+#[test]
+fn corrupt_quantized_codes_are_rejected() {
+    for codes in ["7f8", "7fzz", "7é7", "éé"] {
+        let json = format!(r#"{{"kind": "binary", "dim": 8, "bits": "{}"}}"#, codes);
+        assert!(serde_json::from_str::<QuantizedVector>(&json).is_err(), "{}", codes);
+    }
+}
+
+fn with_id(id: &str, score: f32) -> ScoredDocument {
+    let mut hit = scored(&format!("{}.md", id), score, None);
+    hit.document.id = id.to_string();
+    hit
+}
+
+#[test]
+fn rrf_favours_documents_found_by_both_lists() {
+    let semantic = vec![with_id("a", 0.9), with_id("b", 0.8), with_id("c", 0.7)];
+    let keyword = vec![with_id("c", 0.6), with_id("b", 0.5), with_id("d", 0.4)];
+    let fused = reciprocal_rank_fusion(vec![semantic, keyword]);
+    let ids: Vec<&str> = fused.iter().map(|hit| hit.document.id.as_str()).collect();
+    // Found twice beats a single first place, whatever the similarity
+    assert_eq!(ids, vec!["c", "b", "a", "d"]);
+}
+
+#[test]
+fn rrf_keeps_the_best_similarity_per_document() {
+    let fused = reciprocal_rank_fusion(vec![vec![with_id("a", 0.3)], vec![with_id("a", 0.8)]]);
+    assert_eq!(fused.len(), 1);
+    assert_close(fused[0].score, 0.8);
+}
+
+#[test]
+fn rrf_of_one_list_keeps_its_order() {
+    let list = vec![with_id("x", 0.1), with_id("y", 0.9), with_id("z", 0.5)];
+    let fused = reciprocal_rank_fusion(vec![list]);
+    let ids: Vec<&str> = fused.iter().map(|hit| hit.document.id.as_str()).collect();
+    assert_eq!(ids, vec!["x", "y", "z"]);
+    assert!(reciprocal_rank_fusion(Vec::new()).is_empty());
+}
+
+#[test]
+fn citations_are_distinct_and_in_order_of_first_use() {
+    let reply = "Billing uses Postgres [2]. It was migrated last year [1, 2] and again [3,1].";
+    assert_eq!(parse_citations(reply, 3), vec![2, 1, 3]);
+}
+
+#[test]
+fn citations_outside_the_sources_are_ignored() {
+    assert_eq!(parse_citations("See [0], [4] and [2, 9].", 3), vec![2]);
+    assert!(parse_citations("Arrays like [a, b] or [] are not citations.", 3).is_empty());
+}
+
+fn chunk(index: usize, content: &str, section: &str) -> Document {
+    Document::new(content.to_string(), "doc.md".to_string())
+        .with_position("parent".to_string(), index)
+        .with_location(None, Some(section.to_string()))
+        .with_lines(index * 10 + 1, index * 10 + 9)
+}
+
+fn parent_chunks() -> Vec<Document> {
+    vec![
+        chunk(0, "zero", "Intro"),
+        chunk(1, "one", "Intro"),
+        chunk(2, "two", "Setup"),
+        chunk(3, "three", "Setup"),
+        chunk(4, "four", "Setup"),
+    ]
+}
+
+fn expand(chunks: &[Document], hits: &[(usize, f32)], mode: ContextExpansion, window: usize) -> Vec<ScoredDocument> {
+    let hits = hits
+        .iter()
+        .map(|&(index, score)| ScoredDocument { document: chunks[index].clone(), score })
+        .collect();
+    expand_hits(hits, mode, window, |parent| {
+        chunks.iter().filter(|d| d.metadata.parent_id.as_deref() == Some(parent)).collect()
+    })
+}
+
+#[test]
+fn expansion_off_returns_hits_unchanged() {
+    let chunks = parent_chunks();
+    let expanded = expand(&chunks, &[(2, 0.8)], ContextExpansion::Off, 1);
+    assert_eq!(expanded[0].document.content, "two");
+}
+
+#[test]
+fn expansion_adds_neighbours_and_their_lines() {
+    let chunks = parent_chunks();
+    let expanded = expand(&chunks, &[(2, 0.8)], ContextExpansion::Neighbors, 1);
+    assert_eq!(expanded.len(), 1);
+    assert_eq!(expanded[0].document.content, "one\n\ntwo\n\nthree");
+    assert_eq!(expanded[0].document.metadata.line_start, Some(11));
+    assert_eq!(expanded[0].document.metadata.line_end, Some(39));
+}
+
+#[test]
+fn expansion_merges_touching_hits_into_one_passage() {
+    let chunks = parent_chunks();
+    let expanded = expand(&chunks, &[(3, 0.6), (0, 0.9)], ContextExpansion::Neighbors, 1);
+    assert_eq!(expanded.len(), 1);
+    assert_eq!(expanded[0].document.content, "zero\n\none\n\ntwo\n\nthree\n\nfour");
+    assert_close(expanded[0].score, 0.9);
+}
+
+#[test]
+fn expansion_by_section_stays_within_the_heading() {
+    let chunks = parent_chunks();
+    let expanded = expand(&chunks, &[(3, 0.7)], ContextExpansion::Section, 0);
+    assert_eq!(expanded[0].document.content, "two\n\nthree\n\nfour");
+}
+
+#[test]
+fn expansion_drops_text_shared_by_overlapping_chunks() {
+    let chunks = vec![
+        chunk(0, "The quick brown fox jumps", "Story"),
+        chunk(1, "fox jumps over the lazy dog", "Story"),
+    ];
+    let expanded = expand(&chunks, &[(0, 0.5)], ContextExpansion::Neighbors, 1);
+    assert_eq!(expanded[0].document.content, "The quick brown fox jumps over the lazy dog");
+}
+
+#[test]
+fn expansion_passes_through_hits_without_a_parent() {
+    let loose = ScoredDocument { document: Document::new("loose".to_string(), "x.md".to_string()), score: 0.4 };
+    let expanded = expand_hits(vec![loose], ContextExpansion::Neighbors, 2, |_| Vec::new());
+    assert_eq!(expanded[0].document.content, "loose");
+}
+
+#[test]
+fn hashing_embedder_is_deterministic_and_unit_length() {
+    let embedder = HashingEmbedder::new(128);
+    let vector = embedder.embed("Sourdough needs a lively starter");
+    assert_eq!(vector.len(), 128);
+    assert_eq!(vector, HashingEmbedder::new(128).embed("Sourdough needs a lively starter"));
+    assert_close(vector.iter().map(|x| x * x).sum::<f32>().sqrt(), 1.0);
+}
+
+#[test]
+fn hashing_embedder_names_its_dimension() {
+    assert_eq!(HashingEmbedder::new(256).model_name(), "local-hashing-256");
+    assert_eq!(HashingEmbedder::new(0).embed("anything").len(), 1);
+}
+
+#[test]
+fn hashing_embedder_ignores_case_and_punctuation() {
+    let embedder = HashingEmbedder::new(256);
+    assert_eq!(embedder.embed("Borrow checker!"), embedder.embed("borrow, CHECKER"));
+    assert!(embedder.embed(" ... ").iter().all(|x| *x == 0.0));
+}
+
+#[test]
+fn hashing_embedder_scores_shared_words_higher() {
+    let embedder = HashingEmbedder::new(512);
+    let query = embedder.embed("rust borrow checker");
+    let related = embedder.embed("the borrow checker enforces ownership in rust");
+    let unrelated = embedder.embed("tomato seedlings need sunlight");
+    assert!(cosine_similarity(&query, &related) > cosine_similarity(&query, &unrelated) + 0.3);
+}
+
+#[test]
+fn collection_names_are_safe_directory_names() {
+    assert!(validate_name("work").is_ok());
+    assert!(validate_name("side-project_2").is_ok());
+    assert!(validate_name(&"a".repeat(64)).is_ok());
+    assert!(validate_name("").is_err());
+    assert!(validate_name("Work").is_err());
+    assert!(validate_name(&"a".repeat(65)).is_err());
+    assert!(validate_name("../x").is_err());
+}
+
+#[test]
+fn time_decay_is_off_without_a_positive_half_life() {
+    assert!(TimeDecay::new(0.0, 0.5).is_none());
+    assert!(TimeDecay::new(-3.0, 0.5).is_none());
+}
+
+#[test]
+fn time_decay_clamps_its_weight() {
+    assert_close(TimeDecay::new(30.0, 1.5).unwrap().weight, 1.0);
+    assert_close(TimeDecay::new(30.0, -0.5).unwrap().weight, 0.0);
+}
+
+#[test]
+fn time_decay_halves_the_decaying_part_each_half_life() {
+    let decay = TimeDecay::new(30.0, 0.5).unwrap();
+    let now = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+    assert_close(decay.apply(0.8, now, now), 0.8);
+    // Half the score is fixed, the other half halves: 0.8 * (0.5 + 0.25)
+    assert_close(decay.apply(0.8, now - Duration::days(30), now), 0.6);
+    assert_close(decay.apply(0.8, now - Duration::days(60), now), 0.5);
+}
+
+#[test]
+fn time_decay_does_not_boost_future_timestamps() {
+    let decay = TimeDecay::new(7.0, 1.0).unwrap();
+    let now = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+    assert_close(decay.apply(0.7, now + Duration::days(10), now), 0.7);
+}
+
+fn write(root: &Path, relative: &str, contents: &[u8]) {
+    let path = root.join(relative);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, contents).unwrap();
+}
+
+fn notes_tree() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write(dir.path(), "notes/a.md", b"alpha");
+    write(dir.path(), "notes/b.txt", b"beta");
+    write(dir.path(), "notes/draft.md", b"not yet");
+    write(dir.path(), &format!("notes/{}", IGNORE_FILENAME), b"draft.md\n");
+    write(dir.path(), "src/main.rs", b"fn main() {}");
+    write(dir.path(), "target/out.rs", b"generated");
+    write(dir.path(), "big.log", &[b'x'; 100]);
+    dir
+}
+
+/// Files and skipped paths found under `root`, relative and sorted.
+fn walked(root: &Path, options: &WalkOptions) -> (Vec<String>, Vec<String>) {
+    let relative = |path: &Path| path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/");
+    let (mut files, mut skipped) = (Vec::new(), Vec::new());
+    for entry in walk_files(root, options).unwrap() {
+        match entry {
+            WalkEntry::File(path) => files.push(relative(&path)),
+            WalkEntry::Skipped(path, _) => skipped.push(relative(&path)),
+        }
+    }
+    files.sort();
+    skipped.sort();
+    (files, skipped)
+}
+
+#[test]
+fn walk_honours_the_ignore_file() {
+    let dir = notes_tree();
+    let (files, skipped) = walked(dir.path(), &WalkOptions::default());
+    assert_eq!(files, vec!["big.log", "notes/a.md", "notes/b.txt", "src/main.rs", "target/out.rs"]);
+    assert!(skipped.is_empty());
+}
+
+#[test]
+fn walk_applies_include_and_exclude_globs() {
+    let dir = notes_tree();
+    let options = WalkOptions {
+        include: vec!["**/*.md".to_string(), "**/*.rs".to_string()],
+        exclude: vec!["target/**".to_string()],
+        ..Default::default()
+    };
+    let (files, _) = walked(dir.path(), &options);
+    assert_eq!(files, vec!["notes/a.md", "src/main.rs"]);
+}
+
+#[test]
+fn walk_reports_files_over_the_size_limit() {
+    let dir = notes_tree();
+    let options = WalkOptions { max_file_size: 50, ..Default::default() };
+    let (files, skipped) = walked(dir.path(), &options);
+    assert_eq!(skipped, vec!["big.log"]);
+    assert!(!files.contains(&"big.log".to_string()));
+    assert_eq!(files.len(), 4);
+}
+
+#[test]
+fn text_files_are_told_apart_from_binary_ones() {
+    let dir = tempfile::tempdir().unwrap();
+    write(dir.path(), "README", b"plain words\n");
+    write(dir.path(), "blob", b"\x7fELF\x00\x01\x02");
+    assert!(is_text_file(Path::new("notes.md")));
+    assert!(is_text_file(Path::new("page.html")));
+    assert!(!is_text_file(Path::new("photo.png")));
+    assert!(is_text_file(&dir.path().join("README")));
+    assert!(!is_text_file(&dir.path().join("blob")));
+    assert!(!is_text_file(&dir.path().join("missing")));
+}
+
+fn registry(roots: &[&str]) -> WatchRegistry {
+    let mut registry = WatchRegistry::default();
+    for root in roots {
+        assert!(registry.add(PathBuf::from(root), "default"));
+    }
+    registry
+}
+
+#[test]
+fn watch_root_is_the_deepest_containing_folder() {
+    let registry = registry(&["/home/me/notes", "/home/me/notes/work", "/home/me"]);
+    let root = |path: &str| registry.root_of(Path::new(path));
+    assert_eq!(root("/home/me/notes/work/plan.md"), Some(PathBuf::from("/home/me/notes/work")));
+    assert_eq!(root("/home/me/notes/todo.md"), Some(PathBuf::from("/home/me/notes")));
+    assert_eq!(root("/home/me/notes-old/todo.md"), Some(PathBuf::from("/home/me")));
+}
+
+#[test]
+fn watch_root_is_none_outside_every_folder() {
+    let registry = registry(&["/home/me/notes"]);
+    assert_eq!(registry.root_of(Path::new("/tmp/notes/a.md")), None);
+    assert_eq!(WatchRegistry::default().root_of(Path::new("/home/me/notes/a.md")), None);
+}
+
+#[test]
+fn watch_registry_survives_a_reload() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(WatchRegistry::load_or_default(dir.path()).unwrap().folders.is_empty());
+
+    let mut registry = registry(&["/srv/docs", "/srv/wiki"]);
+    assert!(!registry.add(PathBuf::from("/srv/docs"), "work"));
+    assert!(registry.add(PathBuf::from("/srv/work"), "work"));
+    registry.get_mut(Path::new("/srv/docs")).unwrap().files_synced = 12;
+    assert!(registry.remove(Path::new("/srv/wiki")));
+    assert!(!registry.remove(Path::new("/srv/wiki")));
+    registry.save(dir.path()).unwrap();
+
+    let reloaded = WatchRegistry::load_or_default(dir.path()).unwrap();
+    let folders: Vec<(&Path, &str)> =
+        reloaded.folders.iter().map(|f| (f.path.as_path(), f.collection.as_str())).collect();
+    assert_eq!(folders, vec![(Path::new("/srv/docs"), "default"), (Path::new("/srv/work"), "work")]);
+    assert_eq!(reloaded.get(Path::new("/srv/docs")).unwrap().files_synced, 12);
+}
+
+fn episode(when: DateTime<Utc>, content: &str) -> Episode {
+    Episode { when, content: content.to_string(), score: 0.9 }
+}
+
+/// `when` as `format_episodes` renders it in the local time zone.
+fn local(when: DateTime<Utc>, format: &str) -> String {
+    when.with_timezone(&Local).format(format).to_string()
+}
+
+#[test]
+fn episodes_format_to_just_the_date_when_empty() {
+    let now = Utc.with_ymd_and_hms(2024, 6, 12, 12, 0, 0).unwrap();
+    assert_eq!(format_episodes(&[], now), format!("Today is {}.", local(now, "%A, %-d %B %Y")));
+}
+
+#[test]
+fn episodes_are_dated_oldest_first_and_truncated() {
+    let now = Utc.with_ymd_and_hms(2024, 6, 12, 12, 0, 0).unwrap();
+    let last_week = now - Duration::days(7);
+    let yesterday = now - Duration::days(1);
+    let long = "x".repeat(700);
+    let formatted = format_episodes(&[episode(yesterday, "User: hi\nAssistant: hello"), episode(last_week, &long)], now);
+
+    let expected = format!(
+        "Today is {}.\n\n{}:\n{}...\n\n{}:\nUser: hi\nAssistant: hello",
+        local(now, "%A, %-d %B %Y"),
+        local(last_week, "%A, %-d %B %Y at %H:%M"),
+        "x".repeat(600),
+        local(yesterday, "%A, %-d %B %Y at %H:%M"),
+    );
+    assert_eq!(formatted, expected);
+}